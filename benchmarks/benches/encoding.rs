@@ -0,0 +1,79 @@
+// Copyright 2022 Jonathan Giddy
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use querylizer::{encode_query, Encoder, Form, PercentEncoder};
+use serde::Serialize;
+
+const INPUTS: [(&str, &str); 3] = [
+    ("plain", "the_quick-brown.fox~jumps0123456789"),
+    ("mixed", "the quick/brown fox?jumps=over&the lazy dog"),
+    ("unicode", "überschrift: größe, straße, café"),
+];
+
+fn encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode");
+    for (name, input) in INPUTS {
+        let mut output = String::new();
+        group.bench_with_input(BenchmarkId::new("encoding_fn", name), input, |b, input| {
+            b.iter(|| {
+                output.clear();
+                encode_query.encode(black_box(input), &mut output).unwrap();
+            })
+        });
+        group.bench_with_input(
+            BenchmarkId::new("percent_encoder", name),
+            input,
+            |b, input| {
+                b.iter(|| {
+                    output.clear();
+                    PercentEncoder::QUERY
+                        .encode(black_box(input), &mut output)
+                        .unwrap();
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+#[derive(Serialize)]
+struct Search {
+    q: &'static str,
+    lang: &'static str,
+    page: u32,
+    exact: bool,
+}
+
+fn form(c: &mut Criterion) {
+    let search = Search {
+        q: "rust serde \"query string\"",
+        lang: "en-GB",
+        page: 12,
+        exact: false,
+    };
+    let mut group = c.benchmark_group("form");
+    group.bench_function("encoding_fn", |b| {
+        b.iter(|| Form::to_string("search", black_box(&search), true, &encode_query).unwrap())
+    });
+    group.bench_function("percent_encoder", |b| {
+        b.iter(|| {
+            Form::to_string("search", black_box(&search), true, &PercentEncoder::QUERY).unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, encode, form);
+criterion_main!(benches);
@@ -0,0 +1,345 @@
+// Copyright 2022 Jonathan Giddy
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+use std::marker::PhantomData;
+
+use crate::{
+    BoolFormat, BytesEncoding, DeepArrays, EmptyPolicy, Encoder, FloatFormat, NullPolicy, Options,
+};
+
+/// Build the configuration of a serializer, starting from `builder` on the serializer type.
+///
+/// The serializer type `S` selects the setters that apply to the style, such as `name` and
+/// `explode`. The options setters apply to all styles.
+///
+/// # Example
+///
+/// ```
+/// use querylizer::{encode_query, Form, NullPolicy};
+/// let form = Form::builder(&encode_query)
+///     .name("tag")
+///     .explode(false)
+///     .null_parameters(NullPolicy::Omit)
+///     .build();
+/// assert_eq!(form.to_string(&["a", "b"])?, "tag=a,b");
+/// assert_eq!(form.to_string(&None::<&str>)?, "");
+/// # Ok::<(), querylizer::QuerylizerError>(())
+/// ```
+pub struct Builder<'a, S, F>
+where
+    F: Encoder,
+{
+    pub(crate) config: Config<'a, S, F>,
+}
+
+impl<'a, S, F> Builder<'a, S, F>
+where
+    F: Encoder,
+{
+    pub(crate) fn new(encoder: &'a F, explode: bool) -> Self {
+        Builder {
+            config: Config {
+                name: "",
+                explode,
+                encoder,
+                deep: None,
+                options: Options::default(),
+                style: PhantomData,
+            },
+        }
+    }
+
+    /// Replace all options.
+    pub fn options(mut self, options: Options) -> Self {
+        self.config.options = options;
+        self
+    }
+
+    /// Set how sequences inside a `deepObject` value are written.
+    pub fn deep_arrays(mut self, deep_arrays: DeepArrays) -> Self {
+        self.config.options = self.config.options.deep_arrays(deep_arrays);
+        self
+    }
+
+    /// Set how `None` values in struct fields and map entries are written.
+    pub fn null_fields(mut self, null_fields: NullPolicy) -> Self {
+        self.config.options = self.config.options.null_fields(null_fields);
+        self
+    }
+
+    /// Set how a `None` parameter value is written.
+    pub fn null_parameters(mut self, null_parameters: NullPolicy) -> Self {
+        self.config.options = self.config.options.null_parameters(null_parameters);
+        self
+    }
+
+    /// Set how empty sequences, maps and structs are written.
+    pub fn empty_containers(mut self, empty_containers: EmptyPolicy) -> Self {
+        self.config.options = self.config.options.empty_containers(empty_containers);
+        self
+    }
+
+    /// Set how byte arrays are written.
+    pub fn bytes_encoding(mut self, bytes_encoding: BytesEncoding) -> Self {
+        self.config.options = self.config.options.bytes_encoding(bytes_encoding);
+        self
+    }
+
+    /// Set how floating point numbers are written.
+    pub fn float_format(mut self, float_format: FloatFormat) -> Self {
+        self.config.options = self.config.options.float_format(float_format);
+        self
+    }
+
+    /// Set whether NaN and infinite floating point numbers return an error.
+    pub fn finite_floats(mut self, finite_floats: bool) -> Self {
+        self.config.options = self.config.options.finite_floats(finite_floats);
+        self
+    }
+
+    /// Set how booleans are written.
+    pub fn bool_format(mut self, bool_format: BoolFormat) -> Self {
+        self.config.options = self.config.options.bool_format(bool_format);
+        self
+    }
+
+    /// Create the configuration.
+    pub fn build(self) -> Config<'a, S, F> {
+        self.config
+    }
+}
+
+/// The configuration of a serializer, created using a [`Builder`].
+///
+/// The serializer type `S` provides `to_string` and `extend` methods for the style.
+pub struct Config<'a, S, F>
+where
+    F: Encoder,
+{
+    pub(crate) name: &'a str,
+    pub(crate) explode: bool,
+    pub(crate) encoder: &'a F,
+    // Fields serialized as `deepObject` by `DeepForm`
+    pub(crate) deep: Option<&'a HashSet<&'a str>>,
+    pub(crate) options: Options,
+    style: PhantomData<fn() -> S>,
+}
+
+impl<'a, S, F> Clone for Config<'a, S, F>
+where
+    F: Encoder,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, S, F> Copy for Config<'a, S, F> where F: Encoder {}
+
+// Add `encoded_len` and `to_string_reserved` methods to a `Config` that has an `extend` method.
+macro_rules! reserve_methods {
+    ($what:literal) => {
+        #[doc = concat!("Return the length of ", $what, ", without writing it.")]
+        pub fn encoded_len<T>(&self, value: &T) -> Result<usize, QuerylizerError>
+        where
+            T: ?Sized + Serialize,
+        {
+            let mut counter = crate::CountingSink::new();
+            self.extend(&mut counter, value)?;
+            Ok(counter.count())
+        }
+
+        #[doc = concat!("Serialize ", $what, " into a new string, reserving the output buffer up front.")]
+        pub fn to_string_reserved<T>(&self, value: &T) -> Result<String, QuerylizerError>
+        where
+            T: ?Sized + Serialize,
+        {
+            let mut output = String::with_capacity(self.encoded_len(value)?);
+            self.extend(&mut output, value)?;
+            Ok(output)
+        }
+    };
+}
+
+pub(crate) use reserve_methods;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use serde::Serialize;
+
+    use crate::{
+        encode_cookie, encode_path, encode_query, passthrough, BoolFormat, Cookie, DeepArrays,
+        DeepForm, DeepObject, EmptyPolicy, Form, Label, Matrix, NullPolicy, Options, PipeDelimited,
+        QuerylizerError, Simple, SpaceDelimited,
+    };
+
+    #[test]
+    fn test_form() -> Result<(), QuerylizerError> {
+        let form = Form::builder(&encode_query).name("a b").build();
+        assert_eq!(form.to_string(&[1, 2])?, "a%20b=1&a%20b=2");
+        let form = Form::builder(&passthrough)
+            .name("v")
+            .explode(false)
+            .bool_format(BoolFormat::OneZero)
+            .build();
+        let mut s = "x=1&".to_owned();
+        form.extend(&mut s, &[true, false])?;
+        assert_eq!(s, "x=1&v=1,0");
+        Ok(())
+    }
+
+    #[test]
+    fn test_path_styles() -> Result<(), QuerylizerError> {
+        let v = ["a", "b"];
+        assert_eq!(Simple::builder(&passthrough).build().to_string(&v)?, "a,b");
+        let label = Label::builder(&passthrough).explode(true).build();
+        assert_eq!(label.to_string(&v)?, ".a.b");
+        let matrix = Matrix::builder(&passthrough).name("m").build();
+        assert_eq!(matrix.to_string(&v)?, ";m=a,b");
+        let matrix = Matrix::builder(&passthrough)
+            .name("m")
+            .explode(true)
+            .build();
+        assert_eq!(matrix.to_string(&v)?, ";m=a;m=b");
+        Ok(())
+    }
+
+    #[test]
+    fn test_delimited() -> Result<(), QuerylizerError> {
+        let v = ["a", "b"];
+        let space = SpaceDelimited::builder(&encode_query).name("s").build();
+        assert_eq!(space.to_string(&v)?, "s=a%20b");
+        let pipe = PipeDelimited::builder(&passthrough).name("p").build();
+        assert_eq!(pipe.to_string(&v)?, "p=a|b");
+        Ok(())
+    }
+
+    #[test]
+    fn test_deep() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Filter {
+            tags: Vec<&'static str>,
+        }
+        let filter = Filter {
+            tags: vec!["a", "b"],
+        };
+        let deep = DeepObject::builder(&passthrough)
+            .name("f")
+            .deep_arrays(DeepArrays::Indexed)
+            .build();
+        assert_eq!(deep.to_string(&filter)?, "f[tags][0]=a&f[tags][1]=b");
+
+        #[derive(Serialize)]
+        struct Body {
+            x: u32,
+            y: Filter,
+        }
+        let body = Body { x: 1, y: filter };
+        let mut fields = HashSet::new();
+        fields.insert("y");
+        let form = DeepForm::builder(&passthrough).deep(&fields).build();
+        assert_eq!(form.to_string(&body)?, "x=1&y[tags]=a&y[tags]=b");
+        Ok(())
+    }
+
+    #[test]
+    fn test_deep_form_no_deep_fields() {
+        #[derive(Serialize)]
+        struct Body {
+            y: Vec<u32>,
+        }
+        let form = DeepForm::builder(&passthrough).build();
+        assert!(form.to_string(&Body { y: vec![1] }).is_err());
+    }
+
+    #[test]
+    fn test_cookie() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Cookies {
+            a: Option<u32>,
+            b: u32,
+        }
+        let cookie = Cookie::builder(&passthrough)
+            .null_fields(NullPolicy::Empty)
+            .build();
+        assert_eq!(cookie.to_string(&Cookies { a: None, b: 2 })?, "a=; b=2");
+        Ok(())
+    }
+
+    #[test]
+    fn test_reserved() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Filter {
+            color: &'static str,
+            path: &'static str,
+            size: f64,
+        }
+        let filter = Filter {
+            color: "dark red",
+            path: "a/b",
+            size: 1.5,
+        };
+        macro_rules! reserved {
+            ($config:expr) => {{
+                let config = $config;
+                (
+                    config.to_string_reserved(&filter)?,
+                    config.encoded_len(&filter)?,
+                )
+            }};
+        }
+        // Each output, with the length reported by `encoded_len`
+        let outputs = [
+            reserved!(Form::builder(&encode_query).name("f").build()),
+            reserved!(Simple::builder(&encode_path).build()),
+            reserved!(Label::builder(&encode_path).build()),
+            reserved!(Matrix::builder(&encode_path).name("f").build()),
+            reserved!(DeepObject::builder(&encode_query).name("f").build()),
+            reserved!(DeepForm::builder(&encode_query).build()),
+            reserved!(Cookie::builder(&encode_cookie).build()),
+        ];
+        let expected = [
+            "color=dark%20red&path=a%2Fb&size=1.5",
+            "color,dark%20red,path,a%2Fb,size,1.5",
+            ".color,dark%20red,path,a%2Fb,size,1.5",
+            ";f=color,dark%20red,path,a%2Fb,size,1.5",
+            "f[color]=dark%20red&f[path]=a%2Fb&f[size]=1.5",
+            "color=dark%20red&path=a%2Fb&size=1.5",
+            "color=dark%20red; path=a/b; size=1.5",
+        ];
+        for ((s, len), e) in outputs.iter().zip(expected.iter()) {
+            assert_eq!(s, e);
+            assert_eq!(s.len(), *len);
+            assert!(s.capacity() >= s.len());
+        }
+        let space = SpaceDelimited::builder(&encode_query).name("s").build();
+        assert_eq!(space.encoded_len(&["a b", "c"])?, "s=a%20b%20c".len());
+        let pipe = PipeDelimited::builder(&encode_query).name("p").build();
+        assert_eq!(pipe.encoded_len(&["a", "c"])?, "p=a|c".len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_options() -> Result<(), QuerylizerError> {
+        let form = Form::builder(&passthrough)
+            .name("v")
+            .options(Options::new().empty_containers(EmptyPolicy::Empty))
+            .build();
+        assert_eq!(form.to_string(&Vec::<u32>::new())?, "v=");
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+// Copyright 2022 Jonathan Giddy
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::BytesEncoding;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64_URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+const HEX: &[u8; 16] = b"0123456789abcdef";
+
+/// Encode bytes as a string, or return `None` if they are written as a sequence of numbers.
+pub(crate) fn encode_bytes(v: &[u8], encoding: BytesEncoding) -> Option<String> {
+    match encoding {
+        BytesEncoding::Decimal => None,
+        BytesEncoding::Base64 => Some(base64(v, BASE64, true)),
+        BytesEncoding::Base64Url => Some(base64(v, BASE64_URL, false)),
+        BytesEncoding::Hex => {
+            let mut s = String::with_capacity(v.len() * 2);
+            for b in v {
+                s.push(HEX[usize::from(b >> 4)] as char);
+                s.push(HEX[usize::from(b & 0xf)] as char);
+            }
+            Some(s)
+        }
+    }
+}
+
+fn base64(v: &[u8], alphabet: &[u8; 64], pad: bool) -> String {
+    let mut s = String::with_capacity(v.len() / 3 * 4 + 4);
+    for chunk in v.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+        // Each byte of input adds 8 bits, so needs one more 6-bit character
+        for i in 0..=chunk.len() {
+            s.push(alphabet[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+        if pad {
+            for _ in chunk.len()..3 {
+                s.push('=');
+            }
+        }
+    }
+    s
+}
+
+// Bytes that serialize with `serialize_bytes`, like `serde_bytes::Bytes`.
+#[cfg(test)]
+#[derive(Clone, Copy)]
+pub(crate) struct Bytes<'a>(pub(crate) &'a [u8]);
+
+#[cfg(test)]
+impl<'a> serde::Serialize for Bytes<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BytesEncoding;
+
+    use super::encode_bytes;
+
+    #[test]
+    fn test_base64() {
+        // RFC 4648 test vectors
+        let cases = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (input, output) in cases {
+            assert_eq!(
+                encode_bytes(input.as_bytes(), BytesEncoding::Base64).as_deref(),
+                Some(output)
+            );
+        }
+    }
+
+    #[test]
+    fn test_base64_url() {
+        assert_eq!(
+            encode_bytes(&[0xfb, 0xff, 0xbf], BytesEncoding::Base64).as_deref(),
+            Some("+/+/")
+        );
+        assert_eq!(
+            encode_bytes(&[0xfb, 0xff, 0xbf], BytesEncoding::Base64Url).as_deref(),
+            Some("-_-_")
+        );
+        assert_eq!(
+            encode_bytes(b"fo", BytesEncoding::Base64Url).as_deref(),
+            Some("Zm8")
+        );
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(
+            encode_bytes(&[0x00, 0x1f, 0xa0, 0xff], BytesEncoding::Hex).as_deref(),
+            Some("001fa0ff")
+        );
+    }
+
+    #[test]
+    fn test_decimal() {
+        assert_eq!(encode_bytes(&[1, 2], BytesEncoding::Decimal), None);
+    }
+}
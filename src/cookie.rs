@@ -0,0 +1,672 @@
+// Copyright 2022 Jonathan Giddy
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde::{ser, Serialize, Serializer};
+
+use crate::builder::reserve_methods;
+use crate::probe::is_none;
+use crate::sink::Separated;
+use crate::{passthrough, Builder, Config, Encoder, Form, Options, QuerylizerError, Simple, Sink};
+
+enum State {
+    // Top-level outside any container
+    Outer,
+    // Inside a container, but no elements yet
+    InnerFirst,
+    // Inside a container after first element
+    InnerNext,
+}
+
+/// Serialize the cookie parameters of an operation into a `Cookie` header value.
+pub struct Cookie<'s, F, W = String>
+where
+    F: Encoder,
+    W: Sink + ?Sized,
+{
+    output: &'s mut W,
+    encoder: &'s F,
+    options: Options,
+    state: State,
+    // Name of the cookie for the next map value
+    key: String,
+    // A cookie was left out
+    omitted: bool,
+}
+
+impl<'s, F> Cookie<'s, F>
+where
+    F: Encoder,
+{
+    /// Create a builder for a `Cookie` header serializer.
+    pub fn builder(encoder: &'s F) -> Builder<'s, Self, F> {
+        Builder::new(encoder, false)
+    }
+
+    /// Serialize a map or struct of cookie parameters into a new `Cookie` header value.
+    ///
+    /// Each field is a separate cookie, serialized using the `form` style with `explode=false`,
+    /// and cookies are separated with `; `. Use `encode_cookie` to encode any characters in names
+    /// and values that are not RFC 6265 cookie-octets. Items of sequences and maps are still
+    /// separated with unencoded commas, as in the OpenAPI examples.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use querylizer::{encode_cookie, Cookie};
+    /// #[derive(serde::Serialize)]
+    /// struct Cookies {
+    ///     session: &'static str,
+    ///     colors: Vec<&'static str>,
+    /// }
+    /// let c = Cookies { session: "a b;c", colors: vec!["blue", "black"] };
+    /// let s = Cookie::to_string(&c, &encode_cookie).unwrap();
+    /// assert_eq!(s, "session=a%20b%3Bc; colors=blue,black".to_owned());
+    /// ```
+    pub fn to_string<T>(value: &T, encoder: &F) -> Result<String, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        Self::to_string_with_options(value, encoder, &Options::default())
+    }
+
+    /// Serialize cookie parameters into a new `Cookie` header value, using the provided options.
+    pub fn to_string_with_options<T>(
+        value: &T,
+        encoder: &F,
+        options: &Options,
+    ) -> Result<String, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut output = String::new();
+        Self::extend_with_options(&mut output, value, encoder, options)?;
+        Ok(output)
+    }
+}
+
+impl<'s, F, W> Cookie<'s, F, W>
+where
+    F: Encoder,
+    W: Sink + ?Sized,
+{
+    /// Append cookie parameters onto an existing `Cookie` header value.
+    ///
+    /// No separator is added before the first cookie.
+    pub fn extend<T>(output: &mut W, value: &T, encoder: &F) -> Result<(), QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        Self::extend_with_options(output, value, encoder, &Options::default())
+    }
+
+    /// Append cookie parameters onto an existing `Cookie` header value, using the provided
+    /// options.
+    pub fn extend_with_options<T>(
+        output: &mut W,
+        value: &T,
+        encoder: &F,
+        options: &Options,
+    ) -> Result<(), QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut serializer = Cookie {
+            output,
+            encoder,
+            options: *options,
+            state: State::Outer,
+            key: String::new(),
+            omitted: false,
+        };
+        value
+            .serialize(&mut serializer)
+            .map_err(|e| e.in_parameter("cookie", ""))?;
+        Ok(())
+    }
+
+    fn end_cookies(&mut self, rejected: &'static str) -> Result<(), QuerylizerError> {
+        match self.state {
+            State::Outer => unreachable!(),
+            State::InnerFirst => {
+                // There is no name to write for empty cookies, and if every cookie was left out
+                // there is nothing to check
+                self.state = State::Outer;
+                if !self.omitted {
+                    self.options.empty_containers.write_empty(rejected)?;
+                }
+                Ok(())
+            }
+            State::InnerNext => {
+                self.state = State::Outer;
+                Ok(())
+            }
+        }
+    }
+
+    fn serialize_cookie<T>(&mut self, name: &str, value: &T) -> Result<(), QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        if is_none(value)
+            && !self
+                .options
+                .null_fields
+                .write_none()
+                .map_err(|e| e.at_key(name))?
+        {
+            self.omitted = true;
+            return Ok(());
+        }
+        let separator = match self.state {
+            State::Outer => unreachable!(),
+            State::InnerFirst => "",
+            State::InnerNext => "; ",
+        };
+        let mut output = Separated::new(&mut *self.output, separator);
+        Form::extend_cookie(&mut output, name, value, false, self.encoder, &self.options)?;
+        // An omitted cookie needs no separator
+        if output.written() {
+            self.state = State::InnerNext;
+        } else {
+            self.omitted = true;
+        }
+        Ok(())
+    }
+
+    fn begin_cookies(&mut self, rejected: &'static str) -> Result<(), QuerylizerError> {
+        match self.state {
+            State::Outer => {
+                self.state = State::InnerFirst;
+                Ok(())
+            }
+            _ => Err(QuerylizerError::unsupported_nesting(rejected)),
+        }
+    }
+}
+
+impl<'s, F> Config<'s, Cookie<'s, F>, F>
+where
+    F: Encoder,
+{
+    /// Serialize a `Cookie` header value into a new string.
+    pub fn to_string<T>(&self, value: &T) -> Result<String, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        Cookie::to_string_with_options(value, self.encoder, &self.options)
+    }
+
+    /// Append a `Cookie` header value onto an existing string or other sink.
+    pub fn extend<W, T>(&self, output: &mut W, value: &T) -> Result<(), QuerylizerError>
+    where
+        W: Sink + ?Sized,
+        T: ?Sized + Serialize,
+    {
+        Cookie::extend_with_options(output, value, self.encoder, &self.options)
+    }
+
+    reserve_methods!("a `Cookie` header value");
+}
+
+impl<'a, 's, F, W> Serializer for &'a mut Cookie<'s, F, W>
+where
+    F: Encoder,
+    W: Sink + ?Sized,
+{
+    type Ok = ();
+
+    // The error type when some error occurs during serialization.
+    type Error = QuerylizerError;
+
+    // Only maps and structs can provide cookie names, so other containers are rejected.
+    type SerializeSeq = ser::Impossible<(), QuerylizerError>;
+    type SerializeTuple = ser::Impossible<(), QuerylizerError>;
+    type SerializeTupleStruct = ser::Impossible<(), QuerylizerError>;
+    type SerializeTupleVariant = ser::Impossible<(), QuerylizerError>;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn serialize_bool(self, _v: bool) -> Result<Self::Ok, Self::Error> {
+        Err(QuerylizerError::unsupported_value("bool"))
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<Self::Ok, Self::Error> {
+        Err(QuerylizerError::unsupported_value("i8"))
+    }
+    fn serialize_i16(self, _v: i16) -> Result<Self::Ok, Self::Error> {
+        Err(QuerylizerError::unsupported_value("i16"))
+    }
+    fn serialize_i32(self, _v: i32) -> Result<Self::Ok, Self::Error> {
+        Err(QuerylizerError::unsupported_value("i32"))
+    }
+    fn serialize_i64(self, _v: i64) -> Result<Self::Ok, Self::Error> {
+        Err(QuerylizerError::unsupported_value("i64"))
+    }
+    fn serialize_i128(self, _v: i128) -> Result<Self::Ok, Self::Error> {
+        Err(QuerylizerError::unsupported_value("i128"))
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<Self::Ok, Self::Error> {
+        Err(QuerylizerError::unsupported_value("u8"))
+    }
+    fn serialize_u16(self, _v: u16) -> Result<Self::Ok, Self::Error> {
+        Err(QuerylizerError::unsupported_value("u16"))
+    }
+    fn serialize_u32(self, _v: u32) -> Result<Self::Ok, Self::Error> {
+        Err(QuerylizerError::unsupported_value("u32"))
+    }
+    fn serialize_u64(self, _v: u64) -> Result<Self::Ok, Self::Error> {
+        Err(QuerylizerError::unsupported_value("u64"))
+    }
+    fn serialize_u128(self, _v: u128) -> Result<Self::Ok, Self::Error> {
+        Err(QuerylizerError::unsupported_value("u128"))
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Self::Ok, Self::Error> {
+        Err(QuerylizerError::unsupported_value("f32"))
+    }
+    fn serialize_f64(self, _v: f64) -> Result<Self::Ok, Self::Error> {
+        Err(QuerylizerError::unsupported_value("f64"))
+    }
+
+    fn serialize_char(self, _v: char) -> Result<Self::Ok, Self::Error> {
+        Err(QuerylizerError::unsupported_value("char"))
+    }
+
+    fn serialize_str(self, _v: &str) -> Result<Self::Ok, Self::Error> {
+        Err(QuerylizerError::unsupported_value("str"))
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        Err(QuerylizerError::unsupported_value("bytes"))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        // No cookie parameters
+        Ok(())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Err(QuerylizerError::unsupported_value("unit"))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        Err(QuerylizerError::unsupported_value("unit struct"))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        Err(QuerylizerError::unsupported_value("unit variant"))
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Err(QuerylizerError::unsupported_value("seq"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Err(QuerylizerError::unsupported_value("tuple"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Err(QuerylizerError::unsupported_value("tuple struct"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(QuerylizerError::unsupported_value("tuple variant"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        self.begin_cookies("map")?;
+        Ok(self)
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        self.begin_cookies("struct")?;
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        self.begin_cookies("struct variant")?;
+        Ok(self)
+    }
+}
+
+impl<'a, 's, F, W> ser::SerializeMap for &'a mut Cookie<'s, F, W>
+where
+    F: Encoder,
+    W: Sink + ?Sized,
+{
+    type Ok = ();
+    type Error = QuerylizerError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        // The name is encoded when the cookie is written.
+        self.key.clear();
+        Simple::extend_with_options(&mut self.key, key, false, &passthrough, &self.options)
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let key = std::mem::take(&mut self.key);
+        let result = self.serialize_cookie(&key, value);
+        self.key = key;
+        result
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        self.end_cookies("empty map")
+    }
+}
+
+macro_rules! struct_serializer {
+    ($trait:ty) => {
+        impl<'a, 's, F, W> $trait for &'a mut Cookie<'s, F, W>
+        where
+            F: Encoder,
+            W: Sink + ?Sized,
+        {
+            type Ok = ();
+            type Error = QuerylizerError;
+
+            fn serialize_field<T>(
+                &mut self,
+                key: &'static str,
+                value: &T,
+            ) -> Result<(), Self::Error>
+            where
+                T: ?Sized + Serialize,
+            {
+                self.serialize_cookie(key, value)
+            }
+
+            fn end(self) -> Result<(), Self::Error> {
+                self.end_cookies("empty struct")
+            }
+        }
+    };
+}
+
+struct_serializer!(ser::SerializeStruct);
+struct_serializer!(ser::SerializeStructVariant);
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+
+    use crate::{
+        encode_cookie, passthrough, BoolFormat, EmptyPolicy, NullPolicy, Options, QuerylizerError,
+        Unsupported,
+    };
+
+    use super::Cookie;
+
+    #[test]
+    fn test_struct() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Test {
+            id: u32,
+            name: &'static str,
+            flag: bool,
+        }
+        let test = Test {
+            id: 5,
+            name: "Bob",
+            flag: true,
+        };
+        assert_eq!(
+            Cookie::to_string(&test, &encode_cookie)?,
+            "id=5; name=Bob; flag=true"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_map() -> Result<(), QuerylizerError> {
+        let mut m = std::collections::BTreeMap::new();
+        m.insert("b", "2");
+        m.insert("a", "1");
+        assert_eq!(Cookie::to_string(&m, &encode_cookie)?, "a=1; b=2");
+        Ok(())
+    }
+
+    #[test]
+    fn test_containers() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Rgb {
+            #[serde(rename = "R")]
+            r: u32,
+            #[serde(rename = "G")]
+            g: u32,
+        }
+        #[derive(Serialize)]
+        struct Test {
+            colors: Vec<&'static str>,
+            rgb: Rgb,
+        }
+        let test = Test {
+            colors: vec!["blue", "black"],
+            rgb: Rgb { r: 100, g: 200 },
+        };
+        assert_eq!(
+            Cookie::to_string(&test, &encode_cookie)?,
+            "colors=blue,black; rgb=R,100,G,200"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_encoding() -> Result<(), QuerylizerError> {
+        let mut m = std::collections::BTreeMap::new();
+        m.insert("a b", "x=\"1\"; y\\2,3%");
+        assert_eq!(
+            Cookie::to_string(&m, &encode_cookie)?,
+            "a%20b=x%3D%221%22%3B%20y%5C2%2C3%25"
+        );
+        let mut m = std::collections::BTreeMap::new();
+        m.insert("a=b", "c=d");
+        assert_eq!(Cookie::to_string(&m, &encode_cookie)?, "a%3Db=c%3Dd");
+        let mut m = std::collections::BTreeMap::new();
+        m.insert("ab", "x=\"1\"; y\\2,3%");
+        assert_eq!(Cookie::to_string(&m, &passthrough)?, "ab=x=\"1\"; y\\2,3%");
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_names() {
+        for name in ["a b", "a;b", "a/b", "a=b", ""] {
+            let mut m = std::collections::BTreeMap::new();
+            m.insert(name, 1);
+            assert_eq!(
+                Cookie::to_string(&m, &passthrough),
+                Err(QuerylizerError::UnsupportedValue(Unsupported {
+                    style: "cookie",
+                    path: name.to_owned(),
+                    rejected: "invalid cookie name",
+                }))
+            );
+        }
+        // `encode_cookie` encodes the characters that end a name, but not other separators.
+        let mut m = std::collections::BTreeMap::new();
+        m.insert("a/b", 1);
+        assert!(Cookie::to_string(&m, &encode_cookie).is_err());
+    }
+
+    #[test]
+    fn test_none_fields() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Test {
+            a: Option<u32>,
+            b: Option<u32>,
+        }
+        let test = Test {
+            a: None,
+            b: Some(2),
+        };
+        assert_eq!(Cookie::to_string(&test, &encode_cookie)?, "b=2");
+        let options = Options::new().null_fields(NullPolicy::Empty);
+        assert_eq!(
+            Cookie::to_string_with_options(&test, &encode_cookie, &options)?,
+            "a=; b=2"
+        );
+        let test = Test { a: None, b: None };
+        assert_eq!(Cookie::to_string(&test, &encode_cookie)?, "");
+        let options = Options::new().null_fields(NullPolicy::Error);
+        assert_eq!(
+            Cookie::to_string_with_options(&test, &encode_cookie, &options),
+            Err(QuerylizerError::UnsupportedValue(Unsupported {
+                style: "cookie",
+                path: "a".to_owned(),
+                rejected: "none",
+            }))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_extend() -> Result<(), QuerylizerError> {
+        let mut m = std::collections::BTreeMap::new();
+        m.insert("b", 2);
+        let mut s = "a=1; ".to_owned();
+        Cookie::extend(&mut s, &m, &encode_cookie)?;
+        assert_eq!(s, "a=1; b=2");
+        Ok(())
+    }
+
+    #[test]
+    fn test_bool_presence() {
+        #[derive(Serialize)]
+        struct Test {
+            verbose: bool,
+        }
+        // A bare `verbose` is not a valid cookie-pair
+        let options = Options::new().bool_format(BoolFormat::Presence);
+        assert_eq!(
+            Cookie::to_string_with_options(&Test { verbose: true }, &encode_cookie, &options),
+            Err(QuerylizerError::UnsupportedValue(Unsupported {
+                style: "cookie",
+                path: "verbose".to_owned(),
+                rejected: "presence-only bool",
+            }))
+        );
+        assert!(matches!(
+            Cookie::to_string_with_options(&Test { verbose: false }, &encode_cookie, &options),
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
+    }
+
+    #[test]
+    fn test_unsupported() {
+        assert!(matches!(
+            Cookie::to_string(&5u32, &encode_cookie),
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
+        assert!(matches!(
+            Cookie::to_string(&["a", "b"], &encode_cookie),
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
+        assert!(matches!(
+            Cookie::to_string(
+                &std::collections::BTreeMap::<&str, u32>::new(),
+                &encode_cookie
+            ),
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
+    }
+
+    #[test]
+    fn test_empty_containers() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Test {
+            a: Vec<u32>,
+            b: u32,
+        }
+        let test = Test { a: vec![], b: 2 };
+        let options = Options::new().empty_containers(EmptyPolicy::Empty);
+        assert_eq!(
+            Cookie::to_string_with_options(&test, &encode_cookie, &options)?,
+            "a=; b=2"
+        );
+        let options = Options::new().empty_containers(EmptyPolicy::Omit);
+        assert_eq!(
+            Cookie::to_string_with_options(&test, &encode_cookie, &options)?,
+            "b=2"
+        );
+        assert!(matches!(
+            Cookie::to_string(&test, &encode_cookie),
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
+        Ok(())
+    }
+}
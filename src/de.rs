@@ -0,0 +1,1277 @@
+// Copyright 2022 Jonathan Giddy
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use serde::de::value::{CowStrDeserializer, MapDeserializer, SeqDeserializer};
+use serde::de::{self, Deserialize, IntoDeserializer, Unexpected, Visitor};
+
+use crate::QuerylizerError;
+
+// A parsed parameter. Styles are ambiguous without knowing the target type (`a,b,c,d` may be a
+// sequence or a map), so each style provides every view of the input that it supports and the
+// deserializer picks the one requested by the visitor.
+#[derive(Clone)]
+struct Node<'de> {
+    // The whole value as a single string
+    value: Option<Cow<'de, str>>,
+    // The value as a sequence of items
+    items: Option<Vec<Node<'de>>>,
+    // The value as a sequence of key-value pairs
+    entries: Option<Vec<(Node<'de>, Node<'de>)>>,
+    // The key was written without `=`, so an `Option` is `None`
+    null: bool,
+    // No pair has the parameter name, so an `Option` is `None` even if `entries` holds the other
+    // pairs of an exploded value
+    unnamed: bool,
+}
+
+impl<'de> Node<'de> {
+    fn leaf(value: Cow<'de, str>) -> Self {
+        Node {
+            value: Some(value),
+            items: None,
+            entries: None,
+            null: false,
+            unnamed: false,
+        }
+    }
+
+    // The value of a `key=value` pair, or of a bare `key`.
+    fn assigned(value: Option<&'de str>, plus_as_space: bool) -> Result<Self, QuerylizerError> {
+        match value {
+            Some(value) => Ok(Node::leaf(decode(value, plus_as_space)?)),
+            None => Ok(Node {
+                null: true,
+                ..Node::leaf(Cow::Borrowed(""))
+            }),
+        }
+    }
+
+    fn missing() -> Self {
+        Node {
+            value: None,
+            items: None,
+            entries: None,
+            null: false,
+            unnamed: false,
+        }
+    }
+
+    fn into_value(self) -> Result<Cow<'de, str>, QuerylizerError> {
+        self.value
+            .ok_or_else(|| de::Error::custom("expected a single value"))
+    }
+
+    fn parse<T>(self, visitor: &dyn de::Expected) -> Result<T, QuerylizerError>
+    where
+        T: FromStr,
+    {
+        let value = self.into_value()?;
+        value
+            .parse()
+            .map_err(|_| de::Error::invalid_value(Unexpected::Str(&value), visitor))
+    }
+}
+
+fn decode(s: &str, plus_as_space: bool) -> Result<Cow<'_, str>, QuerylizerError> {
+    let utf8_error = |_| de::Error::custom("invalid UTF-8 after percent-decoding");
+    if plus_as_space && s.contains('+') {
+        let replaced = s.replace('+', " ");
+        let decoded = percent_encoding::percent_decode_str(&replaced)
+            .decode_utf8()
+            .map_err(utf8_error)?;
+        Ok(Cow::Owned(decoded.into_owned()))
+    } else {
+        percent_encoding::percent_decode_str(s)
+            .decode_utf8()
+            .map_err(utf8_error)
+    }
+}
+
+fn leaves<'de>(
+    parts: impl Iterator<Item = &'de str>,
+    plus_as_space: bool,
+) -> Result<Vec<Node<'de>>, QuerylizerError> {
+    parts
+        .map(|part| decode(part, plus_as_space).map(Node::leaf))
+        .collect()
+}
+
+// Split an already-decoded value, keeping borrowed values borrowed.
+fn split_decoded<'de>(value: &Cow<'de, str>, delimiter: char) -> Vec<Node<'de>> {
+    match value {
+        Cow::Borrowed(s) => s
+            .split(delimiter)
+            .map(|item| Node::leaf(Cow::Borrowed(item)))
+            .collect(),
+        Cow::Owned(s) => s
+            .split(delimiter)
+            .map(|item| Node::leaf(Cow::Owned(item.to_owned())))
+            .collect(),
+    }
+}
+
+// Pair up alternating keys and values (`key1,value1,key2,value2`).
+fn alternating<'de>(items: Vec<Node<'de>>) -> Option<Vec<(Node<'de>, Node<'de>)>> {
+    if items.len() % 2 == 1 {
+        return None;
+    }
+    let mut entries = Vec::with_capacity(items.len() / 2);
+    let mut iter = items.into_iter();
+    while let (Some(key), Some(value)) = (iter.next(), iter.next()) {
+        entries.push((key, value));
+    }
+    Some(entries)
+}
+
+// Split a `key=value` part. A part without `=` is a bare key with no value.
+fn pair(part: &str) -> (&str, Option<&str>) {
+    match part.split_once('=') {
+        Some((key, value)) => (key, Some(value)),
+        None => (part, None),
+    }
+}
+
+// Split `key=value` parts into entries.
+fn assignments<'de>(
+    parts: impl Iterator<Item = &'de str>,
+    plus_as_space: bool,
+) -> Result<Vec<(Node<'de>, Node<'de>)>, QuerylizerError> {
+    parts
+        .map(|part| {
+            let (key, value) = pair(part);
+            Ok((
+                Node::leaf(decode(key, plus_as_space)?),
+                Node::assigned(value, plus_as_space)?,
+            ))
+        })
+        .collect()
+}
+
+// Split a query string into undecoded `(key, value)` pairs.
+fn query_pairs(input: &str) -> impl Iterator<Item = (&str, Option<&str>)> {
+    input.split('&').filter(|part| !part.is_empty()).map(pair)
+}
+
+// Split items for a path style, treating an empty input as an empty sequence.
+fn path_items(input: &str, delimiter: char) -> impl Iterator<Item = &str> {
+    let mut parts = input.split(delimiter);
+    if input.is_empty() {
+        parts.next();
+    }
+    parts
+}
+
+fn simple_node(input: &str, explode: bool) -> Result<Node<'_>, QuerylizerError> {
+    let items = leaves(path_items(input, ','), false)?;
+    let entries = if explode {
+        Some(assignments(path_items(input, ','), false)?)
+    } else {
+        alternating(leaves(path_items(input, ','), false)?)
+    };
+    Ok(Node {
+        value: Some(decode(input, false)?),
+        items: Some(items),
+        entries,
+        // There is no name to leave out, so an empty value is `None`
+        null: input.is_empty(),
+        unnamed: false,
+    })
+}
+
+fn label_node(input: &str, explode: bool) -> Result<Node<'_>, QuerylizerError> {
+    let input = input
+        .strip_prefix('.')
+        .ok_or_else(|| de::Error::custom("expected a `label` value starting with `.`"))?;
+    let delimiter = if explode { '.' } else { ',' };
+    let items = leaves(path_items(input, delimiter), false)?;
+    let entries = if explode {
+        Some(assignments(path_items(input, '.'), false)?)
+    } else {
+        alternating(leaves(path_items(input, ','), false)?)
+    };
+    Ok(Node {
+        value: Some(decode(input, false)?),
+        items: Some(items),
+        entries,
+        // There is no name to leave out, so an empty value is `None`
+        null: input.is_empty(),
+        unnamed: false,
+    })
+}
+
+fn matrix_node<'de>(
+    name: &str,
+    input: &'de str,
+    explode: bool,
+) -> Result<Node<'de>, QuerylizerError> {
+    let input = input
+        .strip_prefix(';')
+        .ok_or_else(|| de::Error::custom("expected a `matrix` value starting with `;`"))?;
+    let mut named = Vec::new();
+    let mut entries = Vec::new();
+    for part in path_items(input, ';') {
+        let (key, value) = pair(part);
+        let key = decode(key, false)?;
+        if key == name {
+            named.push(value);
+        }
+        entries.push((Node::leaf(key), Node::assigned(value, false)?));
+    }
+    let null = matches!(named.first(), Some(None));
+    let named = named
+        .into_iter()
+        .map(|value| value.unwrap_or(""))
+        .collect::<Vec<_>>();
+    let value = match named.first() {
+        Some(value) => Some(decode(value, false)?),
+        None => None,
+    };
+    if explode {
+        Ok(Node {
+            value,
+            unnamed: named.is_empty(),
+            items: Some(leaves(named.into_iter(), false)?),
+            entries: Some(entries),
+            null,
+        })
+    } else {
+        match named.first() {
+            Some(value) => Ok(Node {
+                value: Some(decode(value, false)?),
+                items: Some(leaves(path_items(value, ','), false)?),
+                entries: alternating(leaves(path_items(value, ','), false)?),
+                null,
+                unnamed: false,
+            }),
+            None => Ok(Node::missing()),
+        }
+    }
+}
+
+fn form_node<'de>(
+    name: &str,
+    input: &'de str,
+    explode: bool,
+) -> Result<Node<'de>, QuerylizerError> {
+    let mut named = Vec::new();
+    for (key, value) in query_pairs(input) {
+        if decode(key, true)? == name {
+            named.push(value);
+        }
+    }
+    let null = matches!(named.first(), Some(None));
+    let named = named
+        .into_iter()
+        .map(|value| value.unwrap_or(""))
+        .collect::<Vec<_>>();
+    let value = match named.first() {
+        Some(value) => Some(decode(value, true)?),
+        None => None,
+    };
+    if explode {
+        let entries = query_pairs(input)
+            .map(|(key, value)| Ok((Node::leaf(decode(key, true)?), Node::assigned(value, true)?)))
+            .collect::<Result<_, QuerylizerError>>()?;
+        Ok(Node {
+            value,
+            unnamed: named.is_empty(),
+            items: Some(leaves(named.into_iter(), true)?),
+            entries: Some(entries),
+            null,
+        })
+    } else {
+        match named.first() {
+            Some(value) => Ok(Node {
+                value: Some(decode(value, true)?),
+                items: Some(leaves(path_items(value, ','), true)?),
+                entries: alternating(leaves(path_items(value, ','), true)?),
+                null,
+                unnamed: false,
+            }),
+            None => Ok(Node::missing()),
+        }
+    }
+}
+
+fn delimited_node<'de>(
+    name: &str,
+    input: &'de str,
+    delimiter: char,
+) -> Result<Node<'de>, QuerylizerError> {
+    for (key, value) in query_pairs(input) {
+        if decode(key, true)? == name {
+            let null = value.is_none();
+            let raw = value.unwrap_or("");
+            let value = decode(raw, true)?;
+            let items = if value.is_empty() {
+                Vec::new()
+            } else if delimiter == '|' {
+                // The pipe is written as a literal `|`, so split before decoding to keep an encoded
+                // `%7C` inside an item.
+                leaves(raw.split(delimiter), true)?
+            } else {
+                // The space is encoded in the same way as the values, so split after decoding.
+                split_decoded(&value, delimiter)
+            };
+            let entries = alternating(items.clone());
+            return Ok(Node {
+                value: Some(value),
+                items: Some(items),
+                entries,
+                null,
+                unnamed: false,
+            });
+        }
+    }
+    Ok(Node::missing())
+}
+
+// Split a decoded `outer[a][b]` key into `outer` and the bracketed keys, keeping borrowed keys
+// borrowed.
+fn bracketed<'de>(key: &Cow<'de, str>) -> Option<(Cow<'de, str>, Vec<Cow<'de, str>>)> {
+    fn split(key: &str) -> Option<(&str, Vec<&str>)> {
+        let (outer, mut rest) = key.split_once('[')?;
+        let mut inner = Vec::new();
+        loop {
+            let (part, tail) = rest.split_once(']')?;
+            inner.push(part);
+            if tail.is_empty() {
+                return Some((outer, inner));
+            }
+            rest = tail.strip_prefix('[')?;
+        }
+    }
+    match key {
+        Cow::Borrowed(s) => {
+            let (outer, inner) = split(s)?;
+            Some((
+                Cow::Borrowed(outer),
+                inner.into_iter().map(Cow::Borrowed).collect(),
+            ))
+        }
+        Cow::Owned(s) => {
+            let (outer, inner) = split(s)?;
+            Some((
+                Cow::Owned(outer.to_owned()),
+                inner
+                    .into_iter()
+                    .map(|k| Cow::Owned(k.to_owned()))
+                    .collect(),
+            ))
+        }
+    }
+}
+
+// A `deepObject` value collected from its pairs, before the target type is known.
+#[derive(Default)]
+struct Deep<'de> {
+    // Undecoded values of the key, one for each time it is repeated, or `None` for a bare key
+    values: Vec<Option<&'de str>>,
+    // Nested values, in the order their bracketed keys first appear
+    children: Vec<(Cow<'de, str>, Deep<'de>)>,
+}
+
+impl<'de> Deep<'de> {
+    // Add a value below the bracketed keys.
+    fn insert(&mut self, keys: &[Cow<'de, str>], raw: Option<&'de str>) {
+        let (key, rest) = match keys.split_first() {
+            None => {
+                self.values.push(raw);
+                return;
+            }
+            Some(split) => split,
+        };
+        let index = match self.children.iter().position(|(k, _)| k == key) {
+            Some(index) => index,
+            None => {
+                self.children.push((key.clone(), Deep::default()));
+                self.children.len() - 1
+            }
+        };
+        self.children[index].1.insert(rest, raw);
+    }
+
+    // A repeated key is a sequence of its values, and a single value may join items with commas.
+    // Nested keys that are all empty (`key[]`) or indexes (`key[0]`) are also a sequence.
+    fn into_node(self) -> Result<Node<'de>, QuerylizerError> {
+        let null = self.values == [None];
+        let values = self
+            .values
+            .iter()
+            .map(|raw| raw.unwrap_or(""))
+            .collect::<Vec<_>>();
+        let value = match values.first() {
+            Some(raw) => Some(decode(raw, true)?),
+            None => None,
+        };
+        let mut items = match values.len() {
+            0 => None,
+            1 => Some(leaves(path_items(values[0], ','), true)?),
+            _ => Some(leaves(values.into_iter(), true)?),
+        };
+        let nested = !self.children.is_empty();
+        let mut sequence = nested;
+        let mut indexed = Vec::new();
+        let mut appended = Vec::new();
+        let mut entries = Vec::with_capacity(self.children.len());
+        for (key, child) in self.children {
+            if key.is_empty() {
+                let values = child.values.iter().map(|raw| raw.unwrap_or(""));
+                appended.extend(leaves(values, true)?);
+            }
+            let node = child.into_node()?;
+            match key.parse::<usize>() {
+                Ok(index) => indexed.push((index, node.clone())),
+                Err(_) => sequence = sequence && key.is_empty(),
+            }
+            entries.push((Node::leaf(key), node));
+        }
+        if sequence && items.is_none() {
+            indexed.sort_by_key(|(index, _)| *index);
+            let mut nodes = indexed
+                .into_iter()
+                .map(|(_, node)| node)
+                .collect::<Vec<_>>();
+            nodes.append(&mut appended);
+            items = Some(nodes);
+        }
+        Ok(Node {
+            entries: if nested || value.is_none() {
+                Some(entries)
+            } else {
+                None
+            },
+            value,
+            items,
+            null,
+            unnamed: false,
+        })
+    }
+}
+
+fn deep_object_node<'de>(name: &str, input: &'de str) -> Result<Node<'de>, QuerylizerError> {
+    let mut root = Deep::default();
+    for (key, raw) in query_pairs(input) {
+        let key = decode(key, true)?;
+        if key == name {
+            root.insert(&[], raw);
+        } else if let Some((outer, inner)) = bracketed(&key) {
+            if outer == name {
+                root.insert(&inner, raw);
+            }
+        }
+    }
+    root.into_node()
+}
+
+fn deep_form_node<'de>(
+    name: &str,
+    input: &'de str,
+    deep: &HashSet<&str>,
+) -> Result<Node<'de>, QuerylizerError> {
+    let mut value = None;
+    let mut null = false;
+    let mut items = Vec::new();
+    let mut root = Deep::default();
+    for (key, raw) in query_pairs(input) {
+        let key = decode(key, true)?;
+        if let Some((outer, mut inner)) = bracketed(&key) {
+            if deep.contains(outer.as_ref()) {
+                inner.insert(0, outer);
+                root.insert(&inner, raw);
+                continue;
+            }
+        }
+        if key == name {
+            let decoded = decode(raw.unwrap_or(""), true)?;
+            if value.is_none() {
+                value = Some(decoded.clone());
+                null = raw.is_none();
+            }
+            items.push(Node::leaf(decoded));
+        }
+        root.insert(std::slice::from_ref(&key), raw);
+    }
+    Ok(Node {
+        value,
+        items: Some(items),
+        entries: root.into_node()?.entries,
+        null,
+        unnamed: false,
+    })
+}
+
+/// Deserialize a value from an OpenAPI `simple` path parameter.
+///
+/// This is the inverse of [`Simple`](crate::Simple). Items are percent-decoded after splitting.
+///
+/// # Example
+///
+/// ```
+/// use querylizer::from_simple_str;
+/// let v: Vec<String> = from_simple_str("blue,black%20cat", false).unwrap();
+/// assert_eq!(v, vec!["blue", "black cat"]);
+/// ```
+pub fn from_simple_str<'de, T>(input: &'de str, explode: bool) -> Result<T, QuerylizerError>
+where
+    T: Deserialize<'de>,
+{
+    T::deserialize(simple_node(input, explode)?)
+}
+
+/// Deserialize a value from an OpenAPI `label` path parameter.
+///
+/// This is the inverse of [`Label`](crate::Label).
+///
+/// # Example
+///
+/// ```
+/// use querylizer::from_label_str;
+/// let v: Vec<String> = from_label_str(".blue.black", true).unwrap();
+/// assert_eq!(v, vec!["blue", "black"]);
+/// ```
+pub fn from_label_str<'de, T>(input: &'de str, explode: bool) -> Result<T, QuerylizerError>
+where
+    T: Deserialize<'de>,
+{
+    T::deserialize(label_node(input, explode)?)
+}
+
+/// Deserialize a value from an OpenAPI `matrix` path parameter.
+///
+/// This is the inverse of [`Matrix`](crate::Matrix).
+///
+/// # Example
+///
+/// ```
+/// use querylizer::from_matrix_str;
+/// let v: Vec<String> = from_matrix_str("color", ";color=blue;color=black", true).unwrap();
+/// assert_eq!(v, vec!["blue", "black"]);
+/// ```
+pub fn from_matrix_str<'de, T>(
+    name: &str,
+    input: &'de str,
+    explode: bool,
+) -> Result<T, QuerylizerError>
+where
+    T: Deserialize<'de>,
+{
+    T::deserialize(matrix_node(name, input, explode)?)
+}
+
+/// Deserialize a value from an OpenAPI `form` query parameter.
+///
+/// This is the inverse of [`Form`](crate::Form). The input is the query string without the
+/// leading `?`. Pairs for other parameters are ignored, except when deserializing an exploded map
+/// or struct, which uses every pair. A `+` is decoded as a space.
+///
+/// An `Option` is `None` if the parameter is missing or has no `=` (`name`), and `Some` if it has
+/// an empty value (`name=`). An exploded map or struct has no pair with the parameter name, so
+/// read an optional one as a map or struct with `Option` fields instead.
+///
+/// # Example
+///
+/// ```
+/// use querylizer::from_form_str;
+/// #[derive(serde::Deserialize, Debug, PartialEq)]
+/// struct A {
+///     a: i32,
+///     b: String,
+/// }
+/// let a: A = from_form_str("value", "a=12&b=%23hello", true).unwrap();
+/// assert_eq!(a, A { a: 12, b: "#hello".to_owned() });
+/// ```
+pub fn from_form_str<'de, T>(
+    name: &str,
+    input: &'de str,
+    explode: bool,
+) -> Result<T, QuerylizerError>
+where
+    T: Deserialize<'de>,
+{
+    T::deserialize(form_node(name, input, explode)?)
+}
+
+/// Deserialize a value from an OpenAPI `spaceDelimited` query parameter.
+///
+/// This is the inverse of [`SpaceDelimited`](crate::SpaceDelimited).
+///
+/// # Example
+///
+/// ```
+/// use querylizer::from_space_delimited_str;
+/// let v: Vec<String> = from_space_delimited_str("color", "color=blue%20black").unwrap();
+/// assert_eq!(v, vec!["blue", "black"]);
+/// ```
+pub fn from_space_delimited_str<'de, T>(name: &str, input: &'de str) -> Result<T, QuerylizerError>
+where
+    T: Deserialize<'de>,
+{
+    T::deserialize(delimited_node(name, input, ' ')?)
+}
+
+/// Deserialize a value from an OpenAPI `pipeDelimited` query parameter.
+///
+/// This is the inverse of [`PipeDelimited`](crate::PipeDelimited).
+///
+/// # Example
+///
+/// ```
+/// use querylizer::from_pipe_delimited_str;
+/// let v: Vec<String> = from_pipe_delimited_str("color", "color=blue|black").unwrap();
+/// assert_eq!(v, vec!["blue", "black"]);
+/// ```
+pub fn from_pipe_delimited_str<'de, T>(name: &str, input: &'de str) -> Result<T, QuerylizerError>
+where
+    T: Deserialize<'de>,
+{
+    T::deserialize(delimited_node(name, input, '|')?)
+}
+
+/// Deserialize a value from an OpenAPI `deepObject` query parameter.
+///
+/// This is the inverse of [`DeepObject`](crate::DeepObject). Sequences may use any
+/// [`DeepArrays`](crate::DeepArrays) convention.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use querylizer::from_deep_object_str;
+/// let m: BTreeMap<String, u32> = from_deep_object_str("color", "color[R]=100&color[G]=200").unwrap();
+/// assert_eq!(m["R"], 100);
+/// assert_eq!(m["G"], 200);
+/// ```
+pub fn from_deep_object_str<'de, T>(name: &str, input: &'de str) -> Result<T, QuerylizerError>
+where
+    T: Deserialize<'de>,
+{
+    T::deserialize(deep_object_node(name, input)?)
+}
+
+/// Deserialize a form body that mixes `form` and `deepObject` parameters.
+///
+/// This is the inverse of [`DeepForm`](crate::DeepForm). Fields named in `deep` are read from
+/// `field[key]=value` pairs.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashSet;
+/// use querylizer::from_deep_form_str;
+/// #[derive(serde::Deserialize, Debug, PartialEq)]
+/// struct A {
+///     a: i32,
+///     b: String,
+/// }
+/// #[derive(serde::Deserialize, Debug, PartialEq)]
+/// struct B {
+///     x: i32,
+///     y: A,
+/// }
+/// let mut deep = HashSet::new();
+/// deep.insert("y");
+/// let b: B = from_deep_form_str("value", "x=36&y[a]=12&y[b]=%23hello", &deep).unwrap();
+/// assert_eq!(b, B { x: 36, y: A { a: 12, b: "#hello".to_owned() } });
+/// ```
+pub fn from_deep_form_str<'de, T>(
+    name: &str,
+    input: &'de str,
+    deep: &HashSet<&str>,
+) -> Result<T, QuerylizerError>
+where
+    T: Deserialize<'de>,
+{
+    T::deserialize(deep_form_node(name, input, deep)?)
+}
+
+impl<'de> IntoDeserializer<'de, QuerylizerError> for Node<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! deserialize_parse {
+    ($deserialize:ident, $visit:ident) => {
+        fn $deserialize<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            let v = self.parse(&visitor)?;
+            visitor.$visit(v)
+        }
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Node<'de> {
+    type Error = QuerylizerError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if self.value.is_some() {
+            self.deserialize_str(visitor)
+        } else if self.entries.is_some() {
+            self.deserialize_map(visitor)
+        } else {
+            self.deserialize_seq(visitor)
+        }
+    }
+
+    deserialize_parse!(deserialize_bool, visit_bool);
+    deserialize_parse!(deserialize_i8, visit_i8);
+    deserialize_parse!(deserialize_i16, visit_i16);
+    deserialize_parse!(deserialize_i32, visit_i32);
+    deserialize_parse!(deserialize_i64, visit_i64);
+    deserialize_parse!(deserialize_i128, visit_i128);
+    deserialize_parse!(deserialize_u8, visit_u8);
+    deserialize_parse!(deserialize_u16, visit_u16);
+    deserialize_parse!(deserialize_u32, visit_u32);
+    deserialize_parse!(deserialize_u64, visit_u64);
+    deserialize_parse!(deserialize_u128, visit_u128);
+    deserialize_parse!(deserialize_f32, visit_f32);
+    deserialize_parse!(deserialize_f64, visit_f64);
+    deserialize_parse!(deserialize_char, visit_char);
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.into_value()? {
+            Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+            Cow::Owned(s) => visitor.visit_string(s),
+        }
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let items = self
+            .items
+            .ok_or_else(|| de::Error::custom("expected a sequence"))?;
+        let mut bytes = Vec::with_capacity(items.len());
+        for item in items {
+            bytes.push(item.parse(&visitor)?);
+        }
+        visitor.visit_byte_buf(bytes)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    // A missing key, or a bare key without `=`, is `None`. An empty value (`name=`) is `Some`, so
+    // `Option<String>` keeps empty strings.
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let present = !self.unnamed
+            && match &self.value {
+                Some(_) => !self.null,
+                None => {
+                    matches!(&self.items, Some(items) if !items.is_empty())
+                        || matches!(&self.entries, Some(entries) if !entries.is_empty())
+                }
+            };
+        if present {
+            visitor.visit_some(self)
+        } else {
+            visitor.visit_none()
+        }
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Some(value) if !value.is_empty() => {
+                Err(de::Error::invalid_value(Unexpected::Str(&value), &visitor))
+            }
+            _ => visitor.visit_unit(),
+        }
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let items = self
+            .items
+            .ok_or_else(|| de::Error::custom("expected a sequence"))?;
+        let mut seq = SeqDeserializer::new(items.into_iter());
+        let value = visitor.visit_seq(&mut seq)?;
+        seq.end()?;
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let entries = self
+            .entries
+            .ok_or_else(|| de::Error::custom("expected keys and values"))?;
+        let mut map = MapDeserializer::new(entries.into_iter());
+        let value = visitor.visit_map(&mut map)?;
+        map.end()?;
+        Ok(value)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let variant: CowStrDeserializer<'de, QuerylizerError> =
+            self.into_value()?.into_deserializer();
+        visitor.visit_enum(variant)
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashSet};
+
+    use serde::{Deserialize, Serialize};
+
+    use crate::{encode_query, DeepArrays, DeepObject, Options, QuerylizerError};
+
+    use super::{
+        from_deep_form_str, from_deep_object_str, from_form_str, from_label_str, from_matrix_str,
+        from_pipe_delimited_str, from_simple_str, from_space_delimited_str,
+    };
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Rgb {
+        #[serde(rename = "R")]
+        r: u32,
+        #[serde(rename = "G")]
+        g: u32,
+        #[serde(rename = "B")]
+        b: u32,
+    }
+
+    const RGB: Rgb = Rgb {
+        r: 100,
+        g: 200,
+        b: 150,
+    };
+
+    #[test]
+    fn test_simple() -> Result<(), QuerylizerError> {
+        assert_eq!(from_simple_str::<u32>("5", false)?, 5);
+        assert_eq!(
+            from_simple_str::<String>("blue%20moon", false)?,
+            "blue moon"
+        );
+        assert_eq!(
+            from_simple_str::<Vec<String>>("blue,black,brown", false)?,
+            vec!["blue", "black", "brown"]
+        );
+        assert_eq!(
+            from_simple_str::<Vec<String>>("", false)?,
+            Vec::<String>::new()
+        );
+        assert_eq!(from_simple_str::<Rgb>("R,100,G,200,B,150", false)?, RGB);
+        assert_eq!(from_simple_str::<Rgb>("R=100,G=200,B=150", true)?, RGB);
+        Ok(())
+    }
+
+    #[test]
+    fn test_label() -> Result<(), QuerylizerError> {
+        assert_eq!(from_label_str::<f64>(".0.25", false)?, 0.25);
+        assert_eq!(
+            from_label_str::<Vec<String>>(".blue,black", false)?,
+            vec!["blue", "black"]
+        );
+        assert_eq!(
+            from_label_str::<Vec<String>>(".blue.black", true)?,
+            vec!["blue", "black"]
+        );
+        assert_eq!(from_label_str::<Rgb>(".R,100,G,200,B,150", false)?, RGB);
+        assert_eq!(from_label_str::<Rgb>(".R=100.G=200.B=150", true)?, RGB);
+        assert!(from_label_str::<String>("blue", false).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_matrix() -> Result<(), QuerylizerError> {
+        assert_eq!(
+            from_matrix_str::<String>("color", ";color=blue", false)?,
+            "blue"
+        );
+        assert_eq!(
+            from_matrix_str::<Option<String>>("color", ";color", false)?,
+            None
+        );
+        assert_eq!(
+            from_matrix_str::<Vec<String>>("color", ";color=blue,black", false)?,
+            vec!["blue", "black"]
+        );
+        assert_eq!(
+            from_matrix_str::<Vec<String>>("color", ";color=blue;color=black", true)?,
+            vec!["blue", "black"]
+        );
+        assert_eq!(
+            from_matrix_str::<Rgb>("color", ";color=R,100,G,200,B,150", false)?,
+            RGB
+        );
+        assert_eq!(
+            from_matrix_str::<Rgb>("color", ";R=100;G=200;B=150", true)?,
+            RGB
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_form() -> Result<(), QuerylizerError> {
+        assert!(from_form_str::<bool>("flag", "flag=true", false)?);
+        assert_eq!(
+            from_form_str::<String>("color", "x=1&color=a+red%26car", false)?,
+            "a red&car"
+        );
+        assert_eq!(from_form_str::<Option<u32>>("color", "x=1", false)?, None);
+        assert!(from_form_str::<Option<u32>>("color", "color=", false).is_err());
+        assert_eq!(
+            from_form_str::<Option<u32>>("color", "color=3", false)?,
+            Some(3)
+        );
+        assert_eq!(
+            from_form_str::<Vec<String>>("color", "color=blue,black%2Cbrown", false)?,
+            vec!["blue", "black,brown"]
+        );
+        assert_eq!(
+            from_form_str::<Vec<String>>("color", "color=blue&x=1&color=black", true)?,
+            vec!["blue", "black"]
+        );
+        assert_eq!(
+            from_form_str::<Rgb>("color", "color=R,100,G,200,B,150", false)?,
+            RGB
+        );
+        assert_eq!(
+            from_form_str::<Rgb>("color", "R=100&G=200&B=150", true)?,
+            RGB
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_option() -> Result<(), QuerylizerError> {
+        let cases = [
+            ("x=1", None),
+            ("color", None),
+            ("color=", Some("")),
+            ("color=red", Some("red")),
+        ];
+        for (input, expected) in cases {
+            let expected = expected.map(str::to_owned);
+            for explode in [false, true] {
+                assert_eq!(
+                    from_form_str::<Option<String>>("color", input, explode)?,
+                    expected
+                );
+            }
+            assert_eq!(
+                from_deep_object_str::<Option<String>>("color", input)?,
+                expected
+            );
+            assert_eq!(
+                from_pipe_delimited_str::<Option<String>>("color", input)?,
+                expected
+            );
+            let input = format!(";{}", input);
+            for explode in [false, true] {
+                assert_eq!(
+                    from_matrix_str::<Option<String>>("color", &input, explode)?,
+                    expected
+                );
+            }
+        }
+        assert_eq!(from_form_str::<Option<u32>>("limit", "page=2", true)?, None);
+        assert_eq!(
+            from_form_str::<Option<Vec<u32>>>("ids", "page=2", true)?,
+            None
+        );
+        assert_eq!(
+            from_form_str::<Option<Vec<u32>>>("ids", "ids=1&page=2&ids=3", true)?,
+            Some(vec![1, 3])
+        );
+        assert_eq!(
+            from_matrix_str::<Option<Vec<u32>>>("ids", ";page=2", true)?,
+            None
+        );
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Filter {
+            a: Option<String>,
+            b: Option<String>,
+            c: Option<String>,
+        }
+        let filter = Filter {
+            a: None,
+            b: Some("".to_owned()),
+            c: None,
+        };
+        assert_eq!(from_form_str::<Filter>("f", "a&b=", true)?, filter);
+        assert_eq!(from_simple_str::<Filter>("a,b=", true)?, filter);
+        assert_eq!(from_deep_object_str::<Filter>("f", "f[a]&f[b]=")?, filter);
+        assert_eq!(from_simple_str::<Option<String>>("", false)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_delimited() -> Result<(), QuerylizerError> {
+        assert_eq!(
+            from_space_delimited_str::<Vec<String>>("color", "color=blue%20black")?,
+            vec!["blue", "black"]
+        );
+        assert_eq!(
+            from_pipe_delimited_str::<Vec<String>>("color", "color=blue|black")?,
+            vec!["blue", "black"]
+        );
+        assert_eq!(
+            from_pipe_delimited_str::<Rgb>("color", "color=R|100|G|200|B|150")?,
+            RGB
+        );
+        assert_eq!(
+            from_pipe_delimited_str::<Vec<String>>("color", "color=a%7Cb|c")?,
+            vec!["a|b", "c"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_deep_object() -> Result<(), QuerylizerError> {
+        assert_eq!(
+            from_deep_object_str::<Rgb>("color", "color[R]=100&color[G]=200&other=1&color[B]=150")?,
+            RGB
+        );
+        assert_eq!(
+            from_deep_object_str::<Rgb>(
+                "color",
+                "color%5BR%5D=100&color%5BG%5D=200&color%5BB%5D=150"
+            )?,
+            RGB
+        );
+        let m: BTreeMap<String, String> = from_deep_object_str("color", "color[a]=%23hello")?;
+        assert_eq!(m["a"], "#hello");
+        let m: BTreeMap<String, BTreeMap<String, String>> = from_deep_object_str(
+            "customer",
+            "customer[address][city]=Paris&customer[address][zip]=75001",
+        )?;
+        assert_eq!(m["address"]["city"], "Paris");
+        assert_eq!(m["address"]["zip"], "75001");
+        assert_eq!(from_deep_object_str::<u32>("color", "color=5")?, 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_deep_object_arrays() -> Result<(), QuerylizerError> {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Filter {
+            tags: Vec<String>,
+            sizes: Vec<u32>,
+            color: String,
+        }
+        let filter = Filter {
+            tags: vec!["red".to_owned(), "dark blue".to_owned()],
+            sizes: vec![3],
+            color: "green".to_owned(),
+        };
+        for deep_arrays in [
+            DeepArrays::RepeatedKey,
+            DeepArrays::EmptyBrackets,
+            DeepArrays::Indexed,
+            DeepArrays::CommaJoined,
+        ] {
+            let options = Options::new().deep_arrays(deep_arrays);
+            let s = DeepObject::to_string_with_options("f", &filter, &encode_query, &options)?;
+            assert_eq!(from_deep_object_str::<Filter>("f", &s)?, filter, "{}", s);
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Point {
+            x: u32,
+            y: u32,
+        }
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Path {
+            points: Vec<Point>,
+        }
+        let path = Path {
+            points: vec![Point { x: 1, y: 2 }, Point { x: 3, y: 4 }],
+        };
+        let options = Options::new().deep_arrays(DeepArrays::Indexed);
+        let s = DeepObject::to_string_with_options("p", &path, &encode_query, &options)?;
+        assert_eq!(from_deep_object_str::<Path>("p", &s)?, path);
+        // Indexes are sorted, but can still be read as map keys
+        assert_eq!(
+            from_deep_object_str::<Vec<String>>("v", "v[1]=b&v[0]=a")?,
+            vec!["a", "b"]
+        );
+        let m: BTreeMap<u32, String> = from_deep_object_str("v", "v[1]=b&v[0]=a")?;
+        assert_eq!(m[&1], "b");
+        assert_eq!(
+            from_deep_object_str::<Path>(
+                "p",
+                "p[points][1][x]=3&p[points][1][y]=4&p[points][0][x]=1&p[points][0][y]=2"
+            )?,
+            path
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_deep_form() -> Result<(), QuerylizerError> {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Outer {
+            a: u32,
+            b: Rgb,
+        }
+        let mut deep = HashSet::new();
+        deep.insert("b");
+        assert_eq!(
+            from_deep_form_str::<Outer>("color", "a=20&b[R]=100&b[G]=200&b[B]=150", &deep)?,
+            Outer { a: 20, b: RGB }
+        );
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Tagged {
+            x: u32,
+            y: BTreeMap<String, Vec<String>>,
+        }
+        deep.insert("y");
+        let tagged: Tagged = from_deep_form_str("v", "x=1&y[tags]=a&y[tags]=b", &deep)?;
+        assert_eq!(tagged.y["tags"], vec!["a", "b"]);
+        assert_eq!(
+            from_deep_form_str::<Vec<u32>>("color", "color=1&color=2", &HashSet::new())?,
+            vec![1, 2]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_unit_variant() -> Result<(), QuerylizerError> {
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum Sort {
+            #[serde(rename = "asc")]
+            Asc,
+            #[serde(rename = "desc")]
+            Desc,
+        }
+        assert_eq!(
+            from_form_str::<Sort>("sort", "sort=desc", false)?,
+            Sort::Desc
+        );
+        assert_eq!(from_simple_str::<Sort>("asc", false)?, Sort::Asc);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(from_simple_str::<u32>("blue", false).is_err());
+        assert!(from_simple_str::<Rgb>("R,100,G", false).is_err());
+        assert!(from_form_str::<u32>("color", "other=1", false).is_err());
+        assert!(from_simple_str::<String>("%FF", false).is_err());
+    }
+}
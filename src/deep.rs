@@ -14,32 +14,62 @@
 
 use serde::{ser, Serialize, Serializer};
 
-use crate::{EncodingFn, QuerylizerError};
-
-enum State {
-    // Top-level outside any container
-    Outer,
-    // Inside a container, but no elements yet
-    InnerFirst,
-    // Inside a container after first element
-    InnerNext,
+use crate::builder::reserve_methods;
+use crate::bytes::encode_bytes;
+use crate::float::write_float;
+use crate::probe::{is_none, key_name};
+use crate::{Builder, Config, DeepArrays, Encoder, Options, QuerylizerError, Sink};
+
+#[derive(PartialEq)]
+enum Kind {
+    // A map or struct, where each element adds its key
+    Object,
+    // A sequence or tuple, where elements follow the `DeepArrays` convention
+    Array,
+}
+
+struct Container {
+    kind: Kind,
+    // Length of the key at the start of the container
+    len: usize,
+    // Number of elements started so far
+    elements: usize,
+    // Description of the container if it has no elements
+    empty: &'static str,
+    // An element was left out, so the container is left out if it has no elements
+    omitted: bool,
 }
 
 /// Serialize a value into an OpenAPI `deepObject` query parameter.
-pub struct DeepObject<'s, F>
+///
+/// Nested maps and structs add a bracketed key for each level (`name[a][b]=value`). Sequences
+/// inside the object are written using the [`DeepArrays`] convention set in the [`Options`].
+pub struct DeepObject<'s, F, W = String>
 where
-    F: for<'a> EncodingFn<'a>,
+    F: Encoder,
+    W: Sink + ?Sized,
 {
-    output: &'s mut String,
+    output: &'s mut W,
     name: &'s str,
     encoder: &'s F,
-    state: State,
+    options: Options,
+    // Encoded key for the next value (`name[a][b]`)
+    key: String,
+    // Enclosing containers, innermost last
+    containers: Vec<Container>,
+    // A pair has been written, so the next pair needs a separator
+    separate: bool,
 }
 
 impl<'s, F> DeepObject<'s, F>
 where
-    F: for<'a> EncodingFn<'a>,
+    F: Encoder,
 {
+    /// Create a builder for a `deepObject` serializer.
+    pub fn builder(encoder: &'s F) -> Builder<'s, Self, F> {
+        Builder::new(encoder, false)
+    }
+
     /// Serialize a `deepObject` value into a new string to be used for web requests.
     ///
     /// # Example
@@ -60,21 +90,49 @@ where
     /// assert_eq!(s, "value[a]=12&value[b]=%23hello".to_owned());
     /// ```
     pub fn to_string<T>(name: &str, value: &T, encoder: &F) -> Result<String, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        Self::to_string_with_options(name, value, encoder, &Options::default())
+    }
+
+    /// Serialize a `deepObject` value into a new string, using the provided options.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use querylizer::{encode_query, DeepArrays, DeepObject, Options};
+    /// #[derive(serde::Serialize)]
+    /// struct A {
+    ///     tags: Vec<&'static str>,
+    /// }
+    /// let a = A { tags: vec!["red", "blue"] };
+    /// let options = Options::new().deep_arrays(DeepArrays::EmptyBrackets);
+    /// let s = DeepObject::to_string_with_options("value", &a, &encode_query, &options).unwrap();
+    /// assert_eq!(s, "value[tags][]=red&value[tags][]=blue".to_owned());
+    /// ```
+    pub fn to_string_with_options<T>(
+        name: &str,
+        value: &T,
+        encoder: &F,
+        options: &Options,
+    ) -> Result<String, QuerylizerError>
     where
         T: ?Sized + Serialize,
     {
         let mut output = String::new();
-        let mut serializer = DeepObject {
-            output: &mut output,
-            name,
-            encoder,
-            state: State::Outer,
-        };
-        value.serialize(&mut serializer)?;
+        Self::extend_with_options(&mut output, name, value, encoder, options)?;
         Ok(output)
     }
+}
 
-    /// Append a `deepObject` value onto an existing string to be used for web requests.
+impl<'s, F, W> DeepObject<'s, F, W>
+where
+    F: Encoder,
+    W: Sink + ?Sized,
+{
+    /// Append a `deepObject` value onto an existing string or other [`Sink`] to be used for web
+    /// requests.
     ///
     /// # Example
     ///
@@ -96,10 +154,24 @@ where
     /// assert_eq!(s, "https://example.com/v1/?value[a]=12&value[b]=%23hello".to_owned());
     /// ```
     pub fn extend<T>(
-        output: &mut String,
+        output: &mut W,
+        name: &str,
+        value: &T,
+        encoder: &F,
+    ) -> Result<(), QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        Self::extend_with_options(output, name, value, encoder, &Options::default())
+    }
+
+    /// Append a `deepObject` value onto an existing string, using the provided options.
+    pub fn extend_with_options<T>(
+        output: &mut W,
         name: &str,
         value: &T,
         encoder: &F,
+        options: &Options,
     ) -> Result<(), QuerylizerError>
     where
         T: ?Sized + Serialize,
@@ -108,16 +180,155 @@ where
             output,
             name,
             encoder,
-            state: State::Outer,
+            options: *options,
+            key: String::new(),
+            containers: Vec::new(),
+            separate: false,
         };
-        value.serialize(&mut serializer)?;
+        value
+            .serialize(&mut serializer)
+            .map_err(|e| e.in_parameter("deepObject", name))?;
         Ok(())
     }
+
+    // Start a container, with `empty` as the rejected value if it has no elements.
+    fn begin_container(
+        &mut self,
+        kind: Kind,
+        rejected: &'static str,
+        empty: &'static str,
+    ) -> Result<(), QuerylizerError> {
+        match self.containers.last() {
+            None => {
+                if kind == Kind::Array {
+                    return Err(QuerylizerError::unsupported_value(rejected));
+                }
+                self.key.clear();
+                self.encoder.encode(self.name, &mut self.key)?;
+            }
+            Some(parent) => {
+                // Only indexed keys can identify which item a nested value belongs to.
+                if parent.kind == Kind::Array && self.options.deep_arrays != DeepArrays::Indexed {
+                    return Err(QuerylizerError::unsupported_nesting(rejected));
+                }
+            }
+        }
+        self.containers.push(Container {
+            kind,
+            len: self.key.len(),
+            elements: 0,
+            empty,
+            omitted: false,
+        });
+        Ok(())
+    }
+
+    // Start an element of the innermost container, returning its index.
+    fn begin_element(&mut self) -> usize {
+        let container = match self.containers.last_mut() {
+            None => unreachable!(),
+            Some(container) => container,
+        };
+        self.key.truncate(container.len);
+        if container.kind == Kind::Array {
+            match self.options.deep_arrays {
+                DeepArrays::RepeatedKey | DeepArrays::CommaJoined => {}
+                DeepArrays::EmptyBrackets => self.key.push_str("[]"),
+                DeepArrays::Indexed => {
+                    let mut buffer = itoa::Buffer::new();
+                    self.key.push('[');
+                    self.key.push_str(buffer.format(container.elements));
+                    self.key.push(']');
+                }
+            }
+        }
+        container.elements += 1;
+        container.elements - 1
+    }
+
+    // Leave out the element started by `begin_element`, such as a `None` map value.
+    fn omit_element(&mut self) {
+        if let Some(container) = self.containers.last_mut() {
+            container.elements -= 1;
+            container.omitted = true;
+        }
+    }
+
+    // Leave out an element without starting it, such as a `None` struct field.
+    fn skip_element(&mut self) {
+        if let Some(container) = self.containers.last_mut() {
+            container.omitted = true;
+        }
+    }
+
+    fn end_container(&mut self) -> Result<(), QuerylizerError> {
+        match self.containers.pop() {
+            None => unreachable!(),
+            Some(Container {
+                elements: 0,
+                empty,
+                len,
+                omitted: false,
+                ..
+            }) => {
+                self.key.truncate(len);
+                if self.options.empty_containers.write_empty(empty)? {
+                    if self.separate {
+                        self.output.write_char('&')?;
+                    }
+                    self.output.write_str(&self.key)?;
+                    self.output.write_char('=')?;
+                    self.separate = true;
+                }
+                Ok(())
+            }
+            Some(container) => {
+                self.key.truncate(container.len);
+                Ok(())
+            }
+        }
+    }
 }
 
-impl<'a, 's, F> Serializer for &'a mut DeepObject<'s, F>
+impl<'s, F> Builder<'s, DeepObject<'s, F>, F>
 where
-    F: for<'b> EncodingFn<'b>,
+    F: Encoder,
+{
+    /// Set the parameter name.
+    pub fn name(mut self, name: &'s str) -> Self {
+        self.config.name = name;
+        self
+    }
+}
+
+impl<'s, F> Config<'s, DeepObject<'s, F>, F>
+where
+    F: Encoder,
+{
+    /// Serialize a `deepObject` value into a new string.
+    pub fn to_string<T>(&self, value: &T) -> Result<String, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        DeepObject::to_string_with_options(self.name, value, self.encoder, &self.options)
+    }
+
+    /// Append a `deepObject` value onto an existing string or other sink.
+    pub fn extend<W, T>(&self, output: &mut W, value: &T) -> Result<(), QuerylizerError>
+    where
+        W: Sink + ?Sized,
+        T: ?Sized + Serialize,
+    {
+        DeepObject::extend_with_options(output, self.name, value, self.encoder, &self.options)
+    }
+
+    reserve_methods!("a `deepObject` value");
+}
+
+impl<'a, 's, F, W> Serializer for &'a mut DeepObject<'s, F, W>
+where
+    F: Encoder,
+    W: Sink + ?Sized,
 {
     type Ok = ();
 
@@ -137,7 +348,10 @@ where
     type SerializeStructVariant = Self;
 
     fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
-        self.serialize_str(if v { "true" } else { "false" })
+        match self.options.bool_format.text(v) {
+            Some(s) => self.serialize_str(s),
+            None => Err(QuerylizerError::unsupported_value("presence-only bool")),
+        }
     }
 
     fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
@@ -179,12 +393,12 @@ where
     }
 
     fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
-        let mut buffer = dtoa::Buffer::new();
-        self.serialize_str(buffer.format(v))
+        let options = self.options;
+        write_float(v, &options, |s| self.serialize_str(s))
     }
     fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
-        let mut buffer = dtoa::Buffer::new();
-        self.serialize_str(buffer.format(v))
+        let options = self.options;
+        write_float(v, &options, |s| self.serialize_str(s))
     }
 
     fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
@@ -195,23 +409,53 @@ where
     }
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
-        if let State::Outer = self.state {
-            self.output.extend(self.encoder.call(self.name));
-            self.output.push('=');
+        match self.containers.last() {
+            None => {
+                self.encoder.encode(self.name, self.output)?;
+                self.output.write_char('=')?;
+            }
+            Some(container)
+                if container.kind == Kind::Array
+                    && container.elements > 1
+                    && self.options.deep_arrays == DeepArrays::CommaJoined =>
+            {
+                self.output.write_char(',')?;
+            }
+            Some(_) => {
+                if self.separate {
+                    self.output.write_char('&')?;
+                }
+                self.output.write_str(&self.key)?;
+                self.output.write_char('=')?;
+            }
         }
-        self.output.extend(self.encoder.call(v));
+        self.separate = true;
+        self.encoder.encode(v, self.output)?;
         Ok(())
     }
 
-    fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok, Self::Error> {
-        Err(QuerylizerError::UnsupportedValue)
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        if let Some(s) = encode_bytes(v, self.options.bytes_encoding) {
+            return self.serialize_str(&s);
+        }
+        use ser::SerializeSeq;
+        let mut seq_serializer = self.serialize_seq(Some(v.len()))?;
+        for byte in v {
+            seq_serializer.serialize_element(byte)?;
+        }
+        SerializeSeq::end(seq_serializer)?;
+        Ok(())
     }
 
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
-        if let State::Outer = self.state {
-            self.serialize_str("")
+        if self.containers.is_empty() {
+            if self.options.null_parameters.write_none()? {
+                self.serialize_str("")
+            } else {
+                Ok(())
+            }
         } else {
-            Err(QuerylizerError::UnsupportedNesting)
+            Err(QuerylizerError::unsupported_nesting("none"))
         }
     }
 
@@ -219,36 +463,32 @@ where
     where
         T: ?Sized + Serialize,
     {
-        if let State::Outer = self.state {
-            value.serialize(self)
-        } else {
-            Err(QuerylizerError::UnsupportedNesting)
-        }
+        value.serialize(self)
     }
 
     fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
-        if let State::Outer = self.state {
-            self.serialize_str("")
+        if self.containers.is_empty() {
+            if self.options.null_parameters.write_none()? {
+                self.serialize_str("")
+            } else {
+                Ok(())
+            }
         } else {
-            Err(QuerylizerError::UnsupportedNesting)
+            Err(QuerylizerError::unsupported_nesting("unit"))
         }
     }
 
     fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
-        Err(QuerylizerError::UnsupportedNesting)
+        Err(QuerylizerError::unsupported_value("unit struct"))
     }
 
     fn serialize_unit_variant(
         self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
-        if let State::Outer = self.state {
-            self.serialize_str("")
-        } else {
-            Err(QuerylizerError::UnsupportedNesting)
-        }
+        self.serialize_str(variant)
     }
 
     fn serialize_newtype_struct<T>(
@@ -276,11 +516,13 @@ where
     }
 
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
-        Err(QuerylizerError::UnsupportedValue)
+        self.begin_container(Kind::Array, "seq", "empty sequence")?;
+        Ok(self)
     }
 
     fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
-        Err(QuerylizerError::UnsupportedValue)
+        self.begin_container(Kind::Array, "tuple", "empty sequence")?;
+        Ok(self)
     }
 
     fn serialize_tuple_struct(
@@ -288,7 +530,8 @@ where
         _name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleStruct, Self::Error> {
-        Err(QuerylizerError::UnsupportedValue)
+        self.begin_container(Kind::Array, "tuple struct", "empty sequence")?;
+        Ok(self)
     }
 
     fn serialize_tuple_variant(
@@ -298,17 +541,13 @@ where
         _variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
-        Err(QuerylizerError::UnsupportedValue)
+        self.begin_container(Kind::Array, "tuple variant", "empty sequence")?;
+        Ok(self)
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
-        match self.state {
-            State::Outer => {
-                self.state = State::InnerFirst;
-                Ok(self)
-            }
-            _ => Err(QuerylizerError::UnsupportedNesting),
-        }
+        self.begin_container(Kind::Object, "map", "empty map")?;
+        Ok(self)
     }
 
     fn serialize_struct(
@@ -316,13 +555,8 @@ where
         _name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
-        match self.state {
-            State::Outer => {
-                self.state = State::InnerFirst;
-                Ok(self)
-            }
-            _ => Err(QuerylizerError::UnsupportedNesting),
-        }
+        self.begin_container(Kind::Object, "struct", "empty struct")?;
+        Ok(self)
     }
 
     fn serialize_struct_variant(
@@ -332,34 +566,31 @@ where
         _variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
-        match self.state {
-            State::Outer => {
-                self.state = State::InnerFirst;
-                Ok(self)
-            }
-            _ => Err(QuerylizerError::UnsupportedNesting),
-        }
+        self.begin_container(Kind::Object, "struct variant", "empty struct")?;
+        Ok(self)
     }
 }
 
 macro_rules! seq_serializer {
     ($trait:ty, $serialize:ident) => {
-        impl<'a, 's, F> $trait for &'a mut DeepObject<'s, F>
+        impl<'a, 's, F, W> $trait for &'a mut DeepObject<'s, F, W>
         where
-            F: for<'b> EncodingFn<'b>,
+            F: Encoder,
+            W: Sink + ?Sized,
         {
             type Ok = ();
             type Error = QuerylizerError;
 
-            fn $serialize<T>(&mut self, _value: &T) -> Result<(), Self::Error>
+            fn $serialize<T>(&mut self, value: &T) -> Result<(), Self::Error>
             where
                 T: ?Sized + Serialize,
             {
-                Err(QuerylizerError::UnsupportedValue)
+                let index = self.begin_element();
+                value.serialize(&mut **self).map_err(|e| e.at_index(index))
             }
 
             fn end(self) -> Result<(), Self::Error> {
-                Err(QuerylizerError::UnsupportedValue)
+                self.end_container()
             }
         }
     };
@@ -370,9 +601,10 @@ seq_serializer!(ser::SerializeTuple, serialize_element);
 seq_serializer!(ser::SerializeTupleStruct, serialize_field);
 seq_serializer!(ser::SerializeTupleVariant, serialize_field);
 
-impl<'a, 's, F> ser::SerializeMap for &'a mut DeepObject<'s, F>
+impl<'a, 's, F, W> ser::SerializeMap for &'a mut DeepObject<'s, F, W>
 where
-    F: for<'b> EncodingFn<'b>,
+    F: Encoder,
+    W: Sink + ?Sized,
 {
     type Ok = ();
     type Error = QuerylizerError;
@@ -381,45 +613,48 @@ where
     where
         T: ?Sized + Serialize,
     {
-        match self.state {
-            State::Outer => unreachable!(),
-            State::InnerFirst => {
-                self.state = State::InnerNext;
-            }
-            State::InnerNext => {
-                self.output.push('&');
-            }
-        }
-        self.output.extend(self.encoder.call(self.name));
-        self.output.push('[');
-        key.serialize(&mut **self)
+        self.begin_element();
+        self.key.push('[');
+        crate::Simple::extend_with_options(&mut self.key, key, false, self.encoder, &self.options)?;
+        self.key.push(']');
+        Ok(())
     }
 
     fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
         T: ?Sized + Serialize,
     {
-        self.output.push_str("]=");
-        value.serialize(&mut **self)
+        if !is_none(value) {
+            value.serialize(&mut **self)
+        } else if self.options.null_fields.write_none()? {
+            "".serialize(&mut **self)
+        } else {
+            self.omit_element();
+            Ok(())
+        }
+    }
+
+    fn serialize_entry<K, V>(&mut self, key: &K, value: &V) -> Result<(), Self::Error>
+    where
+        K: ?Sized + Serialize,
+        V: ?Sized + Serialize,
+    {
+        self.serialize_key(key)?;
+        self.serialize_value(value)
+            .map_err(|e| e.at_key(&key_name(key)))
     }
 
     fn end(self) -> Result<(), Self::Error> {
-        match self.state {
-            State::Outer => unreachable!(),
-            State::InnerFirst => Err(QuerylizerError::UnsupportedValue),
-            State::InnerNext => {
-                self.state = State::Outer;
-                Ok(())
-            }
-        }
+        self.end_container()
     }
 }
 
 macro_rules! struct_serializer {
     ($trait:ty) => {
-        impl<'a, 's, F> $trait for &'a mut DeepObject<'s, F>
+        impl<'a, 's, F, W> $trait for &'a mut DeepObject<'s, F, W>
         where
-            F: for<'b> EncodingFn<'b>,
+            F: Encoder,
+            W: Sink + ?Sized,
         {
             type Ok = ();
             type Error = QuerylizerError;
@@ -432,31 +667,30 @@ macro_rules! struct_serializer {
             where
                 T: ?Sized + Serialize,
             {
-                match self.state {
-                    State::Outer => unreachable!(),
-                    State::InnerFirst => {
-                        self.state = State::InnerNext;
-                    }
-                    State::InnerNext => {
-                        self.output.push('&');
-                    }
+                let none = is_none(value);
+                if none
+                    && !self
+                        .options
+                        .null_fields
+                        .write_none()
+                        .map_err(|e| e.at_key(key))?
+                {
+                    self.skip_element();
+                    return Ok(());
+                }
+                self.begin_element();
+                self.key.push('[');
+                self.encoder.encode(key, &mut self.key)?;
+                self.key.push(']');
+                if none {
+                    "".serialize(&mut **self)
+                } else {
+                    value.serialize(&mut **self).map_err(|e| e.at_key(key))
                 }
-                self.output.extend(self.encoder.call(&self.name));
-                self.output.push('[');
-                self.output.extend(self.encoder.call(key));
-                self.output.push_str("]=");
-                value.serialize(&mut **self)
             }
 
             fn end(self) -> Result<(), Self::Error> {
-                match self.state {
-                    State::Outer => unreachable!(),
-                    State::InnerFirst => Err(QuerylizerError::UnsupportedValue),
-                    State::InnerNext => {
-                        self.state = State::Outer;
-                        Ok(())
-                    }
-                }
+                self.end_container()
             }
         }
     };
@@ -469,7 +703,12 @@ struct_serializer!(ser::SerializeStructVariant);
 mod tests {
     use serde::Serialize;
 
-    use crate::{passthrough, QuerylizerError};
+    use crate::bytes::Bytes;
+    use crate::probe::Pairs;
+    use crate::{
+        passthrough, BytesEncoding, DeepArrays, EmptyPolicy, FloatFormat, NullPolicy, Options,
+        QuerylizerError, Unsupported,
+    };
 
     use super::DeepObject;
 
@@ -594,6 +833,57 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_float_format() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Range {
+            min: f32,
+            max: f64,
+        }
+        let range = Range {
+            min: 0.1,
+            max: 1e21,
+        };
+        let options = Options::new().float_format(FloatFormat::Decimal);
+        assert_eq!(
+            DeepObject::to_string_with_options("r", &range, &passthrough, &options)?,
+            "r[min]=0.1&r[max]=1000000000000000000000"
+        );
+        // Map keys use the same options as values
+        struct Scores;
+        impl Serialize for Scores {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeMap;
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry(&1.5f64, &1)?;
+                map.end()
+            }
+        }
+        let options = Options::new().float_format(FloatFormat::Fixed(2));
+        assert_eq!(
+            DeepObject::to_string_with_options("s", &Scores, &passthrough, &options)?,
+            "s[1.50]=1"
+        );
+        let nested = Pairs(&[("a", Scores)]);
+        assert_eq!(
+            DeepObject::to_string_with_options("s", &nested, &passthrough, &options)?,
+            "s[a][1.50]=1"
+        );
+        let range = Range {
+            min: f32::NAN,
+            max: 1.0,
+        };
+        assert_eq!(
+            DeepObject::to_string("r", &range, &passthrough),
+            Err(QuerylizerError::NonFiniteFloat(Unsupported {
+                style: "deepObject",
+                path: "r.min".to_owned(),
+                rejected: "NaN",
+            }))
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_char() -> Result<(), QuerylizerError> {
         assert_eq!(
@@ -614,9 +904,30 @@ mod tests {
 
     #[test]
     fn test_bytes() -> Result<(), QuerylizerError> {
-        assert_eq!(
+        assert!(matches!(
             DeepObject::to_string("color", b"blue", &passthrough),
-            Err(QuerylizerError::UnsupportedValue)
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_bytes_encoding() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Upload<'a> {
+            data: Bytes<'a>,
+        }
+        let upload = Upload {
+            data: Bytes(b"hi?"),
+        };
+        let options = Options::new().bytes_encoding(BytesEncoding::Hex);
+        assert_eq!(
+            DeepObject::to_string_with_options("u", &upload, &passthrough, &options)?,
+            "u[data]=68693f"
+        );
+        assert_eq!(
+            DeepObject::to_string("u", &upload, &passthrough)?,
+            "u[data]=104&u[data]=105&u[data]=63"
         );
         Ok(())
     }
@@ -630,6 +941,35 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_none_parameter() -> Result<(), QuerylizerError> {
+        let options = Options::new().null_parameters(NullPolicy::Omit);
+        assert_eq!(
+            DeepObject::to_string_with_options::<Option<u32>>(
+                "color",
+                &None,
+                &passthrough,
+                &options
+            )?,
+            ""
+        );
+        let options = Options::new().null_parameters(NullPolicy::Error);
+        assert_eq!(
+            DeepObject::to_string_with_options::<Option<u32>>(
+                "color",
+                &None,
+                &passthrough,
+                &options
+            ),
+            Err(QuerylizerError::UnsupportedValue(Unsupported {
+                style: "deepObject",
+                path: "color".to_owned(),
+                rejected: "none",
+            }))
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_some() -> Result<(), QuerylizerError> {
         assert_eq!(
@@ -649,22 +989,56 @@ mod tests {
     fn test_unit_struct() -> Result<(), QuerylizerError> {
         #[derive(Serialize)]
         struct T {}
-        assert_eq!(
+        assert!(matches!(
             DeepObject::to_string("color", &T {}, &passthrough),
-            Err(QuerylizerError::UnsupportedValue)
-        );
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
+        #[derive(Serialize)]
+        struct U;
+        assert!(matches!(
+            DeepObject::to_string("color", &U, &passthrough),
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
         Ok(())
     }
 
     #[test]
     fn test_unit_variant() -> Result<(), QuerylizerError> {
-        #[derive(Serialize)]
+        #[derive(Serialize, PartialEq, Eq, PartialOrd, Ord)]
         enum E {
             A,
+            #[serde(rename = "b")]
+            B,
+        }
+        #[derive(Serialize)]
+        struct S {
+            sort: E,
+            all: Vec<E>,
         }
         assert_eq!(
             DeepObject::to_string("color", &E::A, &passthrough)?,
-            "color="
+            "color=A"
+        );
+        assert_eq!(
+            DeepObject::to_string("color", &E::B, &passthrough)?,
+            "color=b"
+        );
+        let mut m = std::collections::BTreeMap::new();
+        m.insert(E::A, E::B);
+        assert_eq!(
+            DeepObject::to_string("color", &m, &passthrough)?,
+            "color[A]=b"
+        );
+        assert_eq!(
+            DeepObject::to_string(
+                "color",
+                &S {
+                    sort: E::B,
+                    all: vec![E::A, E::B]
+                },
+                &passthrough
+            )?,
+            "color[sort]=b&color[all]=A&color[all]=b"
         );
         Ok(())
     }
@@ -696,20 +1070,20 @@ mod tests {
     #[test]
     fn test_seq() -> Result<(), QuerylizerError> {
         let v = vec!["blue", "black", "brown"];
-        assert_eq!(
+        assert!(matches!(
             DeepObject::to_string("color", &v, &passthrough),
-            Err(QuerylizerError::UnsupportedValue)
-        );
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
         Ok(())
     }
 
     #[test]
     fn test_tuple() -> Result<(), QuerylizerError> {
         let t = ("blue", "black", "brown");
-        assert_eq!(
+        assert!(matches!(
             DeepObject::to_string("color", &t, &passthrough),
-            Err(QuerylizerError::UnsupportedValue)
-        );
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
         Ok(())
     }
 
@@ -718,10 +1092,10 @@ mod tests {
         #[derive(Serialize)]
         struct Triple(&'static str, &'static str, &'static str);
         let v = Triple("blue", "black", "brown");
-        assert_eq!(
+        assert!(matches!(
             DeepObject::to_string("color", &v, &passthrough),
-            Err(QuerylizerError::UnsupportedValue)
-        );
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
         Ok(())
     }
 
@@ -731,10 +1105,10 @@ mod tests {
         enum E {
             A(u32, char),
         }
-        assert_eq!(
+        assert!(matches!(
             DeepObject::to_string("color", &E::A(5, 'f'), &passthrough),
-            Err(QuerylizerError::UnsupportedValue)
-        );
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
         Ok(())
     }
 
@@ -802,7 +1176,7 @@ mod tests {
     }
 
     #[test]
-    fn test_unsupported_nesting() {
+    fn test_nested() {
         #[derive(Serialize)]
         struct Test {
             #[serde(rename = "R")]
@@ -815,18 +1189,390 @@ mod tests {
 
         #[derive(Serialize)]
         struct Outer {
+            a: u32,
             t: Test,
+            z: u32,
         }
         let test = Outer {
+            a: 1,
             t: Test {
                 r: 100,
                 g: 200,
                 b: 150,
             },
+            z: 2,
         };
         assert_eq!(
+            DeepObject::to_string("color", &test, &passthrough).unwrap(),
+            "color[a]=1&color[t][R]=100&color[t][G]=200&color[t][B]=150&color[z]=2"
+        );
+    }
+
+    #[test]
+    fn test_nested_deep() {
+        #[derive(Serialize)]
+        struct Address {
+            city: &'static str,
+        }
+        #[derive(Serialize)]
+        struct Metadata {
+            address: Address,
+        }
+        let mut m = std::collections::BTreeMap::new();
+        m.insert(
+            "metadata",
+            Metadata {
+                address: Address {
+                    city: "San Francisco",
+                },
+            },
+        );
+        assert_eq!(
+            DeepObject::to_string("customer", &m, &crate::encode_query).unwrap(),
+            "customer[metadata][address][city]=San%20Francisco"
+        );
+    }
+
+    #[test]
+    fn test_nested_empty() {
+        #[derive(Serialize)]
+        struct Outer {
+            t: std::collections::BTreeMap<&'static str, u32>,
+        }
+        let test = Outer {
+            t: std::collections::BTreeMap::new(),
+        };
+        assert!(matches!(
             DeepObject::to_string("color", &test, &passthrough),
-            Err(QuerylizerError::UnsupportedNesting)
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
+    }
+
+    #[derive(Serialize)]
+    struct Filter {
+        tags: Vec<&'static str>,
+        limit: u32,
+    }
+
+    fn filter() -> Filter {
+        Filter {
+            tags: vec!["red", "blue"],
+            limit: 5,
+        }
+    }
+
+    #[test]
+    fn test_array_repeated_key() -> Result<(), QuerylizerError> {
+        assert_eq!(
+            DeepObject::to_string("filter", &filter(), &passthrough)?,
+            "filter[tags]=red&filter[tags]=blue&filter[limit]=5"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_empty_brackets() -> Result<(), QuerylizerError> {
+        let options = Options::new().deep_arrays(DeepArrays::EmptyBrackets);
+        assert_eq!(
+            DeepObject::to_string_with_options("filter", &filter(), &passthrough, &options)?,
+            "filter[tags][]=red&filter[tags][]=blue&filter[limit]=5"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_indexed() -> Result<(), QuerylizerError> {
+        let options = Options::new().deep_arrays(DeepArrays::Indexed);
+        assert_eq!(
+            DeepObject::to_string_with_options("filter", &filter(), &passthrough, &options)?,
+            "filter[tags][0]=red&filter[tags][1]=blue&filter[limit]=5"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_comma_joined() -> Result<(), QuerylizerError> {
+        let options = Options::new().deep_arrays(DeepArrays::CommaJoined);
+        assert_eq!(
+            DeepObject::to_string_with_options("filter", &filter(), &passthrough, &options)?,
+            "filter[tags]=red,blue&filter[limit]=5"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_nested() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Item {
+            id: u32,
+        }
+        #[derive(Serialize)]
+        struct Order {
+            items: Vec<Item>,
+            pairs: Vec<(u32, u32)>,
+        }
+        let order = Order {
+            items: vec![Item { id: 1 }, Item { id: 2 }],
+            pairs: vec![(3, 4)],
+        };
+        let options = Options::new().deep_arrays(DeepArrays::Indexed);
+        assert_eq!(
+            DeepObject::to_string_with_options("order", &order, &passthrough, &options)?,
+            "order[items][0][id]=1&order[items][1][id]=2&order[pairs][0][0]=3&order[pairs][0][1]=4"
+        );
+        for deep_arrays in [
+            DeepArrays::RepeatedKey,
+            DeepArrays::EmptyBrackets,
+            DeepArrays::CommaJoined,
+        ] {
+            let options = Options::new().deep_arrays(deep_arrays);
+            assert!(matches!(
+                DeepObject::to_string_with_options("order", &order, &passthrough, &options),
+                Err(QuerylizerError::UnsupportedNesting(_))
+            ));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_empty() {
+        let filter = Filter {
+            tags: vec![],
+            limit: 5,
+        };
+        assert!(matches!(
+            DeepObject::to_string("filter", &filter, &passthrough),
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
+    }
+
+    #[test]
+    fn test_empty_containers() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Test {
+            tags: Vec<&'static str>,
+            t: std::collections::BTreeMap<&'static str, u32>,
+            limit: u32,
+        }
+        let test = Test {
+            tags: vec![],
+            t: std::collections::BTreeMap::new(),
+            limit: 5,
+        };
+        let options = Options::new().empty_containers(EmptyPolicy::Empty);
+        assert_eq!(
+            DeepObject::to_string_with_options("filter", &test, &passthrough, &options)?,
+            "filter[tags]=&filter[t]=&filter[limit]=5"
+        );
+        let options = options.deep_arrays(DeepArrays::CommaJoined);
+        assert_eq!(
+            DeepObject::to_string_with_options("filter", &test, &passthrough, &options)?,
+            "filter[tags]=&filter[t]=&filter[limit]=5"
+        );
+        let options = Options::new().empty_containers(EmptyPolicy::Omit);
+        assert_eq!(
+            DeepObject::to_string_with_options("filter", &test, &passthrough, &options)?,
+            "filter[limit]=5"
+        );
+        let m: std::collections::BTreeMap<&str, u32> = std::collections::BTreeMap::new();
+        assert_eq!(
+            DeepObject::to_string_with_options("filter", &m, &passthrough, &options)?,
+            ""
+        );
+        let options = Options::new().empty_containers(EmptyPolicy::Empty);
+        assert_eq!(
+            DeepObject::to_string_with_options("filter", &m, &passthrough, &options)?,
+            "filter="
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_none_fields() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Inner {
+            x: Option<u32>,
+            y: Option<u32>,
+        }
+        #[derive(Serialize)]
+        struct Test {
+            a: Option<u32>,
+            b: Option<Inner>,
+            c: Inner,
+        }
+        let test = Test {
+            a: None,
+            b: Some(Inner {
+                x: Some(1),
+                y: None,
+            }),
+            c: Inner {
+                x: None,
+                y: Some(2),
+            },
+        };
+        assert_eq!(
+            DeepObject::to_string("obj", &test, &passthrough)?,
+            "obj[b][x]=1&obj[c][y]=2"
+        );
+        let options = Options::new().null_fields(NullPolicy::Empty);
+        assert_eq!(
+            DeepObject::to_string_with_options("obj", &test, &passthrough, &options)?,
+            "obj[a]=&obj[b][x]=1&obj[b][y]=&obj[c][x]=&obj[c][y]=2"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_none_entries() -> Result<(), QuerylizerError> {
+        let mut m = std::collections::BTreeMap::new();
+        m.insert("a", None);
+        m.insert("b", Some(2));
+        assert_eq!(DeepObject::to_string("obj", &m, &passthrough)?, "obj[b]=2");
+        let options = Options::new().null_fields(NullPolicy::Empty);
+        assert_eq!(
+            DeepObject::to_string_with_options("obj", &m, &passthrough, &options)?,
+            "obj[a]=&obj[b]=2"
         );
+        Ok(())
+    }
+
+    #[test]
+    fn test_none_values() -> Result<(), QuerylizerError> {
+        let pairs = Pairs(&[("a", None), ("b", Some(2))]);
+        assert_eq!(
+            DeepObject::to_string("obj", &pairs, &passthrough)?,
+            "obj[b]=2"
+        );
+        let options = Options::new().null_fields(NullPolicy::Empty);
+        assert_eq!(
+            DeepObject::to_string_with_options("obj", &pairs, &passthrough, &options)?,
+            "obj[a]=&obj[b]=2"
+        );
+        // A map with every entry left out is left out too
+        let only = Pairs(&[("a", None::<u32>)]);
+        assert_eq!(DeepObject::to_string("obj", &only, &passthrough)?, "");
+        #[derive(Serialize)]
+        struct Inner {
+            a: Option<u32>,
+        }
+        #[derive(Serialize)]
+        struct Outer {
+            inner: Inner,
+            b: u32,
+        }
+        let outer = Outer {
+            inner: Inner { a: None },
+            b: 1,
+        };
+        assert_eq!(
+            DeepObject::to_string("obj", &outer, &passthrough)?,
+            "obj[b]=1"
+        );
+        let options = Options::new().null_fields(NullPolicy::Error);
+        assert!(matches!(
+            DeepObject::to_string_with_options("obj", &pairs, &passthrough, &options),
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_none_fields_error() {
+        #[derive(Serialize)]
+        struct Inner {
+            y: Option<u32>,
+        }
+        #[derive(Serialize)]
+        struct Test {
+            b: Inner,
+        }
+        let options = Options::new().null_fields(NullPolicy::Error);
+        assert_eq!(
+            DeepObject::to_string_with_options(
+                "obj",
+                &Test {
+                    b: Inner { y: None }
+                },
+                &passthrough,
+                &options
+            ),
+            Err(QuerylizerError::UnsupportedValue(Unsupported {
+                style: "deepObject",
+                path: "obj.b.y".to_owned(),
+                rejected: "none",
+            }))
+        );
+    }
+
+    #[test]
+    fn test_error_path() {
+        #[derive(Serialize)]
+        struct Filter {
+            tags: Vec<Option<u32>>,
+        }
+        let filter = Filter {
+            tags: vec![Some(1), Some(2), None],
+        };
+        let error = DeepObject::to_string("filter", &filter, &passthrough).unwrap_err();
+        assert_eq!(
+            error,
+            QuerylizerError::UnsupportedNesting(Unsupported {
+                style: "deepObject",
+                path: "filter.tags[2]".to_owned(),
+                rejected: "none",
+            })
+        );
+        assert_eq!(
+            error.to_string(),
+            "nested containers not supported: none at `filter.tags[2]` in deepObject style"
+        );
+
+        let mut m = std::collections::BTreeMap::new();
+        m.insert("a", std::collections::BTreeMap::<u32, u32>::new());
+        assert_eq!(
+            DeepObject::to_string("obj", &m, &passthrough)
+                .unwrap_err()
+                .to_string(),
+            "unsupported value: empty map at `obj.a` in deepObject style"
+        );
+    }
+
+    #[test]
+    fn test_json_value() -> Result<(), QuerylizerError> {
+        use serde_json::json;
+
+        let to_string = |value| DeepObject::to_string("v", &value, &passthrough);
+        assert_eq!(to_string(json!(null))?, "v=");
+        assert_eq!(to_string(json!(true))?, "v=true");
+        assert_eq!(to_string(json!(12))?, "v=12");
+        assert_eq!(to_string(json!(-12))?, "v=-12");
+        assert_eq!(to_string(json!(1.5))?, "v=1.5");
+        assert_eq!(to_string(json!("a b"))?, "v=a b");
+        assert!(matches!(
+            to_string(json!([1, 2])),
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
+        assert_eq!(
+            to_string(json!({
+                "a": {"b": 1, "c": null},
+                "d": [true, "x"],
+                "e": null,
+            }))?,
+            "v[a][b]=1&v[d]=true&v[d]=x"
+        );
+        let options = Options::new()
+            .null_fields(NullPolicy::Empty)
+            .deep_arrays(DeepArrays::Indexed);
+        assert_eq!(
+            DeepObject::to_string_with_options(
+                "v",
+                &json!({"a": {"c": null}, "d": [{"x": 1}]}),
+                &passthrough,
+                &options
+            )?,
+            "v[a][c]=&v[d][0][x]=1"
+        );
+        Ok(())
     }
 }
@@ -16,7 +16,12 @@ use std::collections::HashSet;
 
 use serde::{ser, Serialize, Serializer};
 
-use crate::{EncodingFn, QuerylizerError};
+use crate::builder::reserve_methods;
+use crate::bytes::encode_bytes;
+use crate::float::write_float;
+use crate::probe::{is_none, key_name};
+use crate::sink::Separated;
+use crate::{Builder, Config, Encoder, Options, QuerylizerError, Simple, Sink};
 
 enum State {
     // Top-level outside any container
@@ -28,21 +33,34 @@ enum State {
 }
 
 /// Serialize a value into an OpenAPI form body.
-pub struct DeepForm<'s, F>
+pub struct DeepForm<'s, F, W = String>
 where
-    F: for<'a> EncodingFn<'a>,
+    F: Encoder,
+    W: Sink + ?Sized,
 {
-    output: &'s mut String,
+    output: &'s mut W,
     name: &'s str,
     encoder: &'s F,
+    options: Options,
     state: State,
+    // Index of the next item in a sequence
+    index: usize,
     deep: &'s HashSet<&'s str>,
+    // Encoded key of the next map value
+    key: String,
+    // An element of the current container was left out
+    omitted: bool,
 }
 
 impl<'s, F> DeepForm<'s, F>
 where
-    F: for<'a> EncodingFn<'a>,
+    F: Encoder,
 {
+    /// Create a builder for a form body serializer.
+    pub fn builder(encoder: &'s F) -> Builder<'s, Self, F> {
+        Builder::new(encoder, false)
+    }
+
     /// Serialize a form body into a new string to be used for web requests.
     ///
     /// # Example
@@ -73,28 +91,56 @@ where
         encoder: &F,
         deep: &HashSet<&'s str>,
     ) -> Result<String, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        Self::to_string_with_options(name, value, encoder, deep, &Options::default())
+    }
+
+    /// Serialize a form body into a new string, using the provided options.
+    pub fn to_string_with_options<T>(
+        name: &str,
+        value: &T,
+        encoder: &F,
+        deep: &HashSet<&'s str>,
+        options: &Options,
+    ) -> Result<String, QuerylizerError>
     where
         T: ?Sized + Serialize,
     {
         let mut output = String::new();
-        let mut serializer = DeepForm {
-            output: &mut output,
-            name,
-            encoder,
-            deep,
-            state: State::Outer,
-        };
-        value.serialize(&mut serializer)?;
+        Self::extend_with_options(&mut output, name, value, encoder, deep, options)?;
         Ok(output)
     }
+}
 
-    /// Append a form body onto an existing string to be used for web requests.
+impl<'s, F, W> DeepForm<'s, F, W>
+where
+    F: Encoder,
+    W: Sink + ?Sized,
+{
+    /// Append a form body onto an existing string or other [`Sink`] to be used for web requests.
     pub fn extend<T>(
-        output: &mut String,
+        output: &mut W,
+        name: &str,
+        value: &T,
+        encoder: &F,
+        deep: &HashSet<&'s str>,
+    ) -> Result<(), QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        Self::extend_with_options(output, name, value, encoder, deep, &Options::default())
+    }
+
+    /// Append a form body onto an existing string, using the provided options.
+    pub fn extend_with_options<T>(
+        output: &mut W,
         name: &str,
         value: &T,
         encoder: &F,
         deep: &HashSet<&'s str>,
+        options: &Options,
     ) -> Result<(), QuerylizerError>
     where
         T: ?Sized + Serialize,
@@ -103,17 +149,83 @@ where
             output,
             name,
             encoder,
+            options: *options,
             deep,
             state: State::Outer,
+            index: 0,
+            key: String::new(),
+            omitted: false,
         };
-        value.serialize(&mut serializer)?;
+        value
+            .serialize(&mut serializer)
+            .map_err(|e| e.in_parameter("deepForm", name))?;
+        Ok(())
+    }
+
+    // Finish an empty sequence, map or struct, written as the name with an empty value. A
+    // container with every element left out is left out too.
+    fn end_empty(&mut self, rejected: &'static str) -> Result<(), QuerylizerError> {
+        self.state = State::Outer;
+        if std::mem::take(&mut self.omitted) {
+            return Ok(());
+        }
+        if self.options.empty_containers.write_empty(rejected)? {
+            self.encoder.encode(self.name, self.output)?;
+            self.output.write_char('=')?;
+        }
         Ok(())
     }
 }
 
-impl<'a, 's, F> Serializer for &'a mut DeepForm<'s, F>
+impl<'s, F> Builder<'s, DeepForm<'s, F>, F>
+where
+    F: Encoder,
+{
+    /// Set the parameter name.
+    pub fn name(mut self, name: &'s str) -> Self {
+        self.config.name = name;
+        self
+    }
+
+    /// Set the fields that are serialized as `deepObject` parameters.
+    pub fn deep(mut self, deep: &'s HashSet<&'s str>) -> Self {
+        self.config.deep = Some(deep);
+        self
+    }
+}
+
+impl<'s, F> Config<'s, DeepForm<'s, F>, F>
+where
+    F: Encoder,
+{
+    /// Serialize a form body into a new string.
+    pub fn to_string<T>(&self, value: &T) -> Result<String, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut output = String::new();
+        self.extend(&mut output, value)?;
+        Ok(output)
+    }
+
+    /// Append a form body onto an existing string or other sink.
+    pub fn extend<W, T>(&self, output: &mut W, value: &T) -> Result<(), QuerylizerError>
+    where
+        W: Sink + ?Sized,
+        T: ?Sized + Serialize,
+    {
+        let empty = HashSet::new();
+        let deep = self.deep.unwrap_or(&empty);
+        DeepForm::extend_with_options(output, self.name, value, self.encoder, deep, &self.options)
+    }
+
+    reserve_methods!("a form body");
+}
+
+impl<'a, 's, F, W> Serializer for &'a mut DeepForm<'s, F, W>
 where
-    F: for<'b> EncodingFn<'b>,
+    F: Encoder,
+    W: Sink + ?Sized,
 {
     type Ok = ();
 
@@ -133,7 +245,10 @@ where
     type SerializeStructVariant = Self;
 
     fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
-        self.serialize_str(if v { "true" } else { "false" })
+        match self.options.bool_format.text(v) {
+            Some(s) => self.serialize_str(s),
+            None => Err(QuerylizerError::unsupported_value("presence-only bool")),
+        }
     }
 
     fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
@@ -175,12 +290,12 @@ where
     }
 
     fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
-        let mut buffer = dtoa::Buffer::new();
-        self.serialize_str(buffer.format(v))
+        let options = self.options;
+        write_float(v, &options, |s| self.serialize_str(s))
     }
     fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
-        let mut buffer = dtoa::Buffer::new();
-        self.serialize_str(buffer.format(v))
+        let options = self.options;
+        write_float(v, &options, |s| self.serialize_str(s))
     }
 
     fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
@@ -192,14 +307,17 @@ where
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
         if let State::Outer = self.state {
-            self.output.extend(self.encoder.call(self.name));
-            self.output.push('=');
+            self.encoder.encode(self.name, self.output)?;
+            self.output.write_char('=')?;
         }
-        self.output.extend(self.encoder.call(v));
+        self.encoder.encode(v, self.output)?;
         Ok(())
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        if let Some(s) = encode_bytes(v, self.options.bytes_encoding) {
+            return self.serialize_str(&s);
+        }
         use ser::SerializeSeq;
         let mut seq_serializer = self.serialize_seq(Some(v.len()))?;
         for byte in v {
@@ -211,9 +329,13 @@ where
 
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
         if let State::Outer = self.state {
-            self.serialize_str("")
+            if self.options.null_parameters.write_none()? {
+                self.serialize_str("")
+            } else {
+                Ok(())
+            }
         } else {
-            Err(QuerylizerError::UnsupportedNesting)
+            Err(QuerylizerError::unsupported_nesting("none"))
         }
     }
 
@@ -221,36 +343,32 @@ where
     where
         T: ?Sized + Serialize,
     {
-        if let State::Outer = self.state {
-            value.serialize(self)
-        } else {
-            Err(QuerylizerError::UnsupportedNesting)
-        }
+        value.serialize(self)
     }
 
     fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
         if let State::Outer = self.state {
-            self.serialize_str("")
+            if self.options.null_parameters.write_none()? {
+                self.serialize_str("")
+            } else {
+                Ok(())
+            }
         } else {
-            Err(QuerylizerError::UnsupportedNesting)
+            Err(QuerylizerError::unsupported_nesting("unit"))
         }
     }
 
     fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
-        Err(QuerylizerError::UnsupportedNesting)
+        Err(QuerylizerError::unsupported_value("unit struct"))
     }
 
     fn serialize_unit_variant(
         self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
-        if let State::Outer = self.state {
-            self.serialize_str("")
-        } else {
-            Err(QuerylizerError::UnsupportedNesting)
-        }
+        self.serialize_str(variant)
     }
 
     fn serialize_newtype_struct<T>(
@@ -283,7 +401,7 @@ where
                 self.state = State::InnerFirst;
                 Ok(self)
             }
-            _ => Err(QuerylizerError::UnsupportedNesting),
+            _ => Err(QuerylizerError::unsupported_nesting("seq")),
         }
     }
 
@@ -293,7 +411,7 @@ where
                 self.state = State::InnerFirst;
                 Ok(self)
             }
-            _ => Err(QuerylizerError::UnsupportedNesting),
+            _ => Err(QuerylizerError::unsupported_nesting("tuple")),
         }
     }
 
@@ -307,7 +425,7 @@ where
                 self.state = State::InnerFirst;
                 Ok(self)
             }
-            _ => Err(QuerylizerError::UnsupportedNesting),
+            _ => Err(QuerylizerError::unsupported_nesting("tuple struct")),
         }
     }
 
@@ -323,7 +441,7 @@ where
                 self.state = State::InnerFirst;
                 Ok(self)
             }
-            _ => Err(QuerylizerError::UnsupportedNesting),
+            _ => Err(QuerylizerError::unsupported_nesting("tuple variant")),
         }
     }
 
@@ -333,7 +451,7 @@ where
                 self.state = State::InnerFirst;
                 Ok(self)
             }
-            _ => Err(QuerylizerError::UnsupportedNesting),
+            _ => Err(QuerylizerError::unsupported_nesting("map")),
         }
     }
 
@@ -347,7 +465,7 @@ where
                 self.state = State::InnerFirst;
                 Ok(self)
             }
-            _ => Err(QuerylizerError::UnsupportedNesting),
+            _ => Err(QuerylizerError::unsupported_nesting("struct")),
         }
     }
 
@@ -363,16 +481,17 @@ where
                 self.state = State::InnerFirst;
                 Ok(self)
             }
-            _ => Err(QuerylizerError::UnsupportedNesting),
+            _ => Err(QuerylizerError::unsupported_nesting("struct variant")),
         }
     }
 }
 
 macro_rules! seq_serializer {
     ($trait:ty, $serialize:ident) => {
-        impl<'a, 's, F> $trait for &'a mut DeepForm<'s, F>
+        impl<'a, 's, F, W> $trait for &'a mut DeepForm<'s, F, W>
         where
-            F: for<'b> EncodingFn<'b>,
+            F: Encoder,
+            W: Sink + ?Sized,
         {
             type Ok = ();
             type Error = QuerylizerError;
@@ -385,24 +504,27 @@ macro_rules! seq_serializer {
                     State::Outer => unreachable!(),
                     State::InnerFirst => {
                         self.state = State::InnerNext;
-                        self.output.extend(self.encoder.call(&self.name));
-                        self.output.push('=');
+                        self.encoder.encode(&self.name, self.output)?;
+                        self.output.write_char('=')?;
                     }
                     State::InnerNext => {
-                        self.output.push('&');
-                        self.output.extend(self.encoder.call(&self.name));
-                        self.output.push('=');
+                        self.output.write_char('&')?;
+                        self.encoder.encode(&self.name, self.output)?;
+                        self.output.write_char('=')?;
                     }
                 }
-                value.serialize(&mut **self)
+                let index = self.index;
+                self.index += 1;
+                value.serialize(&mut **self).map_err(|e| e.at_index(index))
             }
 
             fn end(self) -> Result<(), Self::Error> {
                 match self.state {
                     State::Outer => unreachable!(),
-                    State::InnerFirst => Err(QuerylizerError::UnsupportedValue),
+                    State::InnerFirst => self.end_empty("empty sequence"),
                     State::InnerNext => {
                         self.state = State::Outer;
+                        self.index = 0;
                         Ok(())
                     }
                 }
@@ -416,9 +538,10 @@ seq_serializer!(ser::SerializeTuple, serialize_element);
 seq_serializer!(ser::SerializeTupleStruct, serialize_field);
 seq_serializer!(ser::SerializeTupleVariant, serialize_field);
 
-impl<'a, 's, F> ser::SerializeMap for &'a mut DeepForm<'s, F>
+impl<'a, 's, F, W> ser::SerializeMap for &'a mut DeepForm<'s, F, W>
 where
-    F: for<'b> EncodingFn<'b>,
+    F: Encoder,
+    W: Sink + ?Sized,
 {
     type Ok = ();
     type Error = QuerylizerError;
@@ -427,35 +550,52 @@ where
     where
         T: ?Sized + Serialize,
     {
+        // Hold the key until the value shows whether the entry is left out
+        self.key.clear();
+        Simple::extend_with_options(&mut self.key, key, false, self.encoder, &self.options)
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let none = is_none(value);
+        if none && !self.options.null_fields.write_none()? {
+            self.omitted = true;
+            return Ok(());
+        }
         match self.state {
             State::Outer => unreachable!(),
             State::InnerFirst => {
                 self.state = State::InnerNext;
             }
             State::InnerNext => {
-                self.output.push('&');
+                self.output.write_char('&')?;
             }
         }
-        key.serialize(&mut **self)
+        self.output.write_str(&self.key)?;
+        self.output.write_char('=')?;
+        if none {
+            "".serialize(&mut **self)
+        } else {
+            value.serialize(&mut **self)
+        }
     }
 
-    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_entry<K, V>(&mut self, key: &K, value: &V) -> Result<(), Self::Error>
     where
-        T: ?Sized + Serialize,
+        K: ?Sized + Serialize,
+        V: ?Sized + Serialize,
     {
-        match self.state {
-            State::Outer => unreachable!(),
-            _ => {
-                self.output.push('=');
-            }
-        }
-        value.serialize(&mut **self)
+        self.serialize_key(key)?;
+        self.serialize_value(value)
+            .map_err(|e| e.at_key(&key_name(key)))
     }
 
     fn end(self) -> Result<(), Self::Error> {
         match self.state {
             State::Outer => unreachable!(),
-            State::InnerFirst => Err(QuerylizerError::UnsupportedValue),
+            State::InnerFirst => self.end_empty("empty map"),
             State::InnerNext => {
                 self.state = State::Outer;
                 Ok(())
@@ -466,9 +606,10 @@ where
 
 macro_rules! struct_serializer {
     ($trait:ty) => {
-        impl<'a, 's, F> $trait for &'a mut DeepForm<'s, F>
+        impl<'a, 's, F, W> $trait for &'a mut DeepForm<'s, F, W>
         where
-            F: for<'b> EncodingFn<'b>,
+            F: Encoder,
+            W: Sink + ?Sized,
         {
             type Ok = ();
             type Error = QuerylizerError;
@@ -481,17 +622,38 @@ macro_rules! struct_serializer {
             where
                 T: ?Sized + Serialize,
             {
+                let none = is_none(value);
+                if none
+                    && !self
+                        .options
+                        .null_fields
+                        .write_none()
+                        .map_err(|e| e.at_key(key))?
+                {
+                    self.omitted = true;
+                    return Ok(());
+                }
                 if self.deep.contains(key) {
-                    match self.state {
+                    let separator = match self.state {
                         State::Outer => unreachable!(),
-                        State::InnerFirst => {
-                            self.state = State::InnerNext;
-                        }
-                        State::InnerNext => {
-                            self.output.push('&');
-                        }
+                        State::InnerFirst => "",
+                        State::InnerNext => "&",
+                    };
+                    let mut output = Separated::new(&mut *self.output, separator);
+                    crate::DeepObject::extend_with_options(
+                        &mut output,
+                        key,
+                        value,
+                        self.encoder,
+                        &self.options,
+                    )?;
+                    // An omitted field needs no separator
+                    if output.written() {
+                        self.state = State::InnerNext;
+                    } else {
+                        self.omitted = true;
                     }
-                    crate::DeepObject::extend(self.output, key, value, self.encoder)
+                    Ok(())
                 } else {
                     match self.state {
                         State::Outer => unreachable!(),
@@ -499,24 +661,28 @@ macro_rules! struct_serializer {
                             self.state = State::InnerNext;
                         }
                         State::InnerNext => {
-                            self.output.push('&');
+                            self.output.write_char('&')?;
                         }
                     }
                     key.serialize(&mut **self)?;
                     match self.state {
                         State::Outer => unreachable!(),
                         _ => {
-                            self.output.push('=');
+                            self.output.write_char('=')?;
                         }
                     }
-                    value.serialize(&mut **self)
+                    if none {
+                        "".serialize(&mut **self)
+                    } else {
+                        value.serialize(&mut **self).map_err(|e| e.at_key(key))
+                    }
                 }
             }
 
             fn end(self) -> Result<(), Self::Error> {
                 match self.state {
                     State::Outer => unreachable!(),
-                    State::InnerFirst => Err(QuerylizerError::UnsupportedValue),
+                    State::InnerFirst => self.end_empty("empty struct"),
                     State::InnerNext => {
                         self.state = State::Outer;
                         Ok(())
@@ -536,7 +702,11 @@ mod tests {
 
     use serde::Serialize;
 
-    use crate::{passthrough, QuerylizerError};
+    use crate::bytes::Bytes;
+    use crate::probe::Pairs;
+    use crate::{
+        passthrough, BytesEncoding, EmptyPolicy, NullPolicy, Options, QuerylizerError, Unsupported,
+    };
 
     use super::DeepForm;
 
@@ -688,6 +858,28 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_bytes_encoding() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Upload<'a> {
+            data: Bytes<'a>,
+        }
+        let options = Options::new().bytes_encoding(BytesEncoding::Base64);
+        assert_eq!(
+            DeepForm::to_string_with_options(
+                "u",
+                &Upload {
+                    data: Bytes(b"hi?")
+                },
+                &passthrough,
+                &HashSet::new(),
+                &options
+            )?,
+            "data=aGk/"
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_none() -> Result<(), QuerylizerError> {
         assert_eq!(
@@ -697,6 +889,38 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_none_parameter() -> Result<(), QuerylizerError> {
+        let deep = HashSet::new();
+        let options = Options::new().null_parameters(NullPolicy::Omit);
+        assert_eq!(
+            DeepForm::to_string_with_options::<Option<u32>>(
+                "color",
+                &None,
+                &passthrough,
+                &deep,
+                &options
+            )?,
+            ""
+        );
+        let options = Options::new().null_parameters(NullPolicy::Error);
+        assert_eq!(
+            DeepForm::to_string_with_options::<Option<u32>>(
+                "color",
+                &None,
+                &passthrough,
+                &deep,
+                &options
+            ),
+            Err(QuerylizerError::UnsupportedValue(Unsupported {
+                style: "deepForm",
+                path: "color".to_owned(),
+                rejected: "none",
+            }))
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_some() -> Result<(), QuerylizerError> {
         assert_eq!(
@@ -719,22 +943,49 @@ mod tests {
     fn test_unit_struct() -> Result<(), QuerylizerError> {
         #[derive(Serialize)]
         struct T {}
-        assert_eq!(
+        assert!(matches!(
             DeepForm::to_string("color", &T {}, &passthrough, &HashSet::new()),
-            Err(QuerylizerError::UnsupportedValue)
-        );
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
+        #[derive(Serialize)]
+        struct U;
+        assert!(matches!(
+            DeepForm::to_string("color", &U, &passthrough, &HashSet::new()),
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
         Ok(())
     }
 
     #[test]
     fn test_unit_variant() -> Result<(), QuerylizerError> {
-        #[derive(Serialize)]
+        #[derive(Serialize, PartialEq, Eq, PartialOrd, Ord)]
         enum E {
             A,
+            #[serde(rename = "b")]
+            B,
+        }
+        #[derive(Serialize)]
+        struct Inner {
+            sort: E,
+        }
+        #[derive(Serialize)]
+        struct S {
+            sort: E,
+            inner: Inner,
         }
         assert_eq!(
             DeepForm::to_string("color", &E::A, &passthrough, &HashSet::new())?,
-            "color="
+            "color=A"
+        );
+        let mut deep = HashSet::new();
+        deep.insert("inner");
+        let s = S {
+            sort: E::B,
+            inner: Inner { sort: E::A },
+        };
+        assert_eq!(
+            DeepForm::to_string("color", &s, &passthrough, &deep)?,
+            "sort=b&inner[sort]=A"
         );
         Ok(())
     }
@@ -926,9 +1177,141 @@ mod tests {
                 b: 150,
             },
         };
-        assert_eq!(
+        assert!(matches!(
             DeepForm::to_string("color", &test, &passthrough, &HashSet::new()),
-            Err(QuerylizerError::UnsupportedNesting)
+            Err(QuerylizerError::UnsupportedNesting(_))
+        ));
+    }
+
+    #[test]
+    fn test_none_fields() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Inner {
+            x: u32,
+        }
+        #[derive(Serialize)]
+        struct Test {
+            a: Option<u32>,
+            b: Option<Inner>,
+            c: u32,
+        }
+        let test = Test {
+            a: None,
+            b: None,
+            c: 3,
+        };
+        let mut deep = HashSet::new();
+        deep.insert("b");
+        assert_eq!(
+            DeepForm::to_string("obj", &test, &passthrough, &deep)?,
+            "c=3"
         );
+        let options = Options::new().null_fields(NullPolicy::Empty);
+        assert_eq!(
+            DeepForm::to_string_with_options("obj", &test, &passthrough, &deep, &options)?,
+            "a=&b=&c=3"
+        );
+        let test = Test {
+            a: Some(1),
+            b: Some(Inner { x: 2 }),
+            c: 3,
+        };
+        assert_eq!(
+            DeepForm::to_string("obj", &test, &passthrough, &deep)?,
+            "a=1&b[x]=2&c=3"
+        );
+        // A struct with every field left out is left out too
+        #[derive(Serialize)]
+        struct Only {
+            a: Option<u32>,
+            b: Option<Inner>,
+        }
+        let only = Only { a: None, b: None };
+        assert_eq!(DeepForm::to_string("obj", &only, &passthrough, &deep)?, "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_none_values() -> Result<(), QuerylizerError> {
+        let pairs = Pairs(&[("a", None), ("b", Some(2))]);
+        assert_eq!(
+            DeepForm::to_string("obj", &pairs, &passthrough, &HashSet::new())?,
+            "b=2"
+        );
+        let options = Options::new().null_fields(NullPolicy::Empty);
+        assert_eq!(
+            DeepForm::to_string_with_options(
+                "obj",
+                &pairs,
+                &passthrough,
+                &HashSet::new(),
+                &options
+            )?,
+            "a=&b=2"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_path() {
+        #[derive(Serialize)]
+        struct Inner {
+            a: Vec<u32>,
+        }
+        #[derive(Serialize)]
+        struct Outer {
+            x: u32,
+            y: Inner,
+        }
+        let outer = Outer {
+            x: 1,
+            y: Inner { a: vec![] },
+        };
+        let mut deep = HashSet::new();
+        deep.insert("y");
+        assert_eq!(
+            DeepForm::to_string("value", &outer, &passthrough, &deep),
+            Err(QuerylizerError::UnsupportedValue(Unsupported {
+                style: "deepForm",
+                path: "value.y.a".to_owned(),
+                rejected: "empty sequence",
+            }))
+        );
+    }
+
+    #[test]
+    fn test_empty_containers() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Test {
+            a: u32,
+            b: std::collections::BTreeMap<&'static str, u32>,
+            c: u32,
+        }
+        let test = Test {
+            a: 1,
+            b: std::collections::BTreeMap::new(),
+            c: 3,
+        };
+        let mut deep = HashSet::new();
+        deep.insert("b");
+        let options = Options::new().empty_containers(EmptyPolicy::Omit);
+        assert_eq!(
+            DeepForm::to_string_with_options("obj", &test, &passthrough, &deep, &options)?,
+            "a=1&c=3"
+        );
+        let options = Options::new().empty_containers(EmptyPolicy::Empty);
+        assert_eq!(
+            DeepForm::to_string_with_options("obj", &test, &passthrough, &deep, &options)?,
+            "a=1&b=&c=3"
+        );
+        assert_eq!(
+            DeepForm::to_string("obj", &test, &passthrough, &deep),
+            Err(QuerylizerError::UnsupportedValue(Unsupported {
+                style: "deepForm",
+                path: "obj.b".to_owned(),
+                rejected: "empty map",
+            }))
+        );
+        Ok(())
     }
 }
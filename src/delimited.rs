@@ -0,0 +1,435 @@
+// Copyright 2022 Jonathan Giddy
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::marker::PhantomData;
+
+use serde::Serialize;
+
+use crate::builder::reserve_methods;
+use crate::form::Delimiter;
+use crate::{Builder, Config, Encoder, Form, Options, QuerylizerError, Sink};
+
+/// Serialize a value into an OpenAPI `spaceDelimited` query parameter.
+pub struct SpaceDelimited<'s, F>
+where
+    F: Encoder,
+{
+    _marker: PhantomData<&'s F>,
+}
+
+impl<'s, F> SpaceDelimited<'s, F>
+where
+    F: Encoder,
+{
+    /// Create a builder for a `spaceDelimited` serializer.
+    pub fn builder(encoder: &'s F) -> Builder<'s, Self, F> {
+        Builder::new(encoder, false)
+    }
+
+    /// Serialize a `spaceDelimited` value into a new string to be used for web requests.
+    ///
+    /// - sequences and tuples use the name once and items are space-separated
+    ///   (`name=item1%20item2`)
+    /// - maps and structs use the name once and keys and values are space separated
+    ///   (`name=key1%20value1%20key2%20value2`).
+    ///
+    /// The space delimiter is passed through the encoder.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use querylizer::{encode_query, SpaceDelimited};
+    /// let s = SpaceDelimited::to_string("color", &["blue", "black"], &encode_query).unwrap();
+    /// assert_eq!(s, "color=blue%20black".to_owned());
+    /// ```
+    pub fn to_string<T>(name: &str, value: &T, encoder: &F) -> Result<String, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        Self::to_string_with_options(name, value, encoder, &Options::default())
+    }
+
+    /// Serialize a `spaceDelimited` value into a new string, using the provided options.
+    pub fn to_string_with_options<T>(
+        name: &str,
+        value: &T,
+        encoder: &F,
+        options: &Options,
+    ) -> Result<String, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut output = String::new();
+        Self::extend_with_options(&mut output, name, value, encoder, options)?;
+        Ok(output)
+    }
+
+    /// Append a `spaceDelimited` value onto an existing string or other [`Sink`] to be used for web
+    /// requests.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use querylizer::{encode_query, SpaceDelimited};
+    /// let mut s = "https://example.com/v1/?".to_owned();
+    /// SpaceDelimited::extend(&mut s, "color", &["blue", "black"], &encode_query).unwrap();
+    /// assert_eq!(s, "https://example.com/v1/?color=blue%20black".to_owned());
+    /// ```
+    pub fn extend<W, T>(
+        output: &mut W,
+        name: &str,
+        value: &T,
+        encoder: &F,
+    ) -> Result<(), QuerylizerError>
+    where
+        W: Sink + ?Sized,
+        T: ?Sized + Serialize,
+    {
+        Self::extend_with_options(output, name, value, encoder, &Options::default())
+    }
+
+    /// Append a `spaceDelimited` value onto an existing string, using the provided options.
+    pub fn extend_with_options<W, T>(
+        output: &mut W,
+        name: &str,
+        value: &T,
+        encoder: &F,
+        options: &Options,
+    ) -> Result<(), QuerylizerError>
+    where
+        W: Sink + ?Sized,
+        T: ?Sized + Serialize,
+    {
+        Form::extend_delimited(output, name, value, encoder, Delimiter::Space, options)
+    }
+}
+
+impl<'s, F> Builder<'s, SpaceDelimited<'s, F>, F>
+where
+    F: Encoder,
+{
+    /// Set the parameter name.
+    pub fn name(mut self, name: &'s str) -> Self {
+        self.config.name = name;
+        self
+    }
+}
+
+impl<'s, F> Config<'s, SpaceDelimited<'s, F>, F>
+where
+    F: Encoder,
+{
+    /// Serialize a `spaceDelimited` value into a new string.
+    pub fn to_string<T>(&self, value: &T) -> Result<String, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        SpaceDelimited::to_string_with_options(self.name, value, self.encoder, &self.options)
+    }
+
+    /// Append a `spaceDelimited` value onto an existing string or other sink.
+    pub fn extend<W, T>(&self, output: &mut W, value: &T) -> Result<(), QuerylizerError>
+    where
+        W: Sink + ?Sized,
+        T: ?Sized + Serialize,
+    {
+        SpaceDelimited::extend_with_options(output, self.name, value, self.encoder, &self.options)
+    }
+
+    reserve_methods!("a `spaceDelimited` value");
+}
+
+/// Serialize a value into an OpenAPI `pipeDelimited` query parameter.
+pub struct PipeDelimited<'s, F>
+where
+    F: Encoder,
+{
+    _marker: PhantomData<&'s F>,
+}
+
+impl<'s, F> PipeDelimited<'s, F>
+where
+    F: Encoder,
+{
+    /// Create a builder for a `pipeDelimited` serializer.
+    pub fn builder(encoder: &'s F) -> Builder<'s, Self, F> {
+        Builder::new(encoder, false)
+    }
+
+    /// Serialize a `pipeDelimited` value into a new string to be used for web requests.
+    ///
+    /// - sequences and tuples use the name once and items are pipe-separated
+    ///   (`name=item1|item2`)
+    /// - maps and structs use the name once and keys and values are pipe separated
+    ///   (`name=key1|value1|key2|value2`).
+    ///
+    /// The pipe delimiter is written as a literal `|`, so that a `|` inside a value, which
+    /// `encode_query` encodes as `%7C`, stays distinct.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use querylizer::{encode_query, PipeDelimited};
+    /// let s = PipeDelimited::to_string("color", &["blue", "black"], &encode_query).unwrap();
+    /// assert_eq!(s, "color=blue|black".to_owned());
+    /// ```
+    pub fn to_string<T>(name: &str, value: &T, encoder: &F) -> Result<String, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        Self::to_string_with_options(name, value, encoder, &Options::default())
+    }
+
+    /// Serialize a `pipeDelimited` value into a new string, using the provided options.
+    pub fn to_string_with_options<T>(
+        name: &str,
+        value: &T,
+        encoder: &F,
+        options: &Options,
+    ) -> Result<String, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut output = String::new();
+        Self::extend_with_options(&mut output, name, value, encoder, options)?;
+        Ok(output)
+    }
+
+    /// Append a `pipeDelimited` value onto an existing string or other [`Sink`] to be used for web
+    /// requests.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use querylizer::{encode_query, PipeDelimited};
+    /// let mut s = "https://example.com/v1/?".to_owned();
+    /// PipeDelimited::extend(&mut s, "color", &["blue", "black"], &encode_query).unwrap();
+    /// assert_eq!(s, "https://example.com/v1/?color=blue|black".to_owned());
+    /// ```
+    pub fn extend<W, T>(
+        output: &mut W,
+        name: &str,
+        value: &T,
+        encoder: &F,
+    ) -> Result<(), QuerylizerError>
+    where
+        W: Sink + ?Sized,
+        T: ?Sized + Serialize,
+    {
+        Self::extend_with_options(output, name, value, encoder, &Options::default())
+    }
+
+    /// Append a `pipeDelimited` value onto an existing string, using the provided options.
+    pub fn extend_with_options<W, T>(
+        output: &mut W,
+        name: &str,
+        value: &T,
+        encoder: &F,
+        options: &Options,
+    ) -> Result<(), QuerylizerError>
+    where
+        W: Sink + ?Sized,
+        T: ?Sized + Serialize,
+    {
+        Form::extend_delimited(output, name, value, encoder, Delimiter::Pipe, options)
+    }
+}
+
+impl<'s, F> Builder<'s, PipeDelimited<'s, F>, F>
+where
+    F: Encoder,
+{
+    /// Set the parameter name.
+    pub fn name(mut self, name: &'s str) -> Self {
+        self.config.name = name;
+        self
+    }
+}
+
+impl<'s, F> Config<'s, PipeDelimited<'s, F>, F>
+where
+    F: Encoder,
+{
+    /// Serialize a `pipeDelimited` value into a new string.
+    pub fn to_string<T>(&self, value: &T) -> Result<String, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        PipeDelimited::to_string_with_options(self.name, value, self.encoder, &self.options)
+    }
+
+    /// Append a `pipeDelimited` value onto an existing string or other sink.
+    pub fn extend<W, T>(&self, output: &mut W, value: &T) -> Result<(), QuerylizerError>
+    where
+        W: Sink + ?Sized,
+        T: ?Sized + Serialize,
+    {
+        PipeDelimited::extend_with_options(output, self.name, value, self.encoder, &self.options)
+    }
+
+    reserve_methods!("a `pipeDelimited` value");
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+
+    use crate::{encode_query, passthrough, EmptyPolicy, Options, QuerylizerError};
+
+    use super::{PipeDelimited, SpaceDelimited};
+
+    #[test]
+    fn test_str() -> Result<(), QuerylizerError> {
+        assert_eq!(
+            SpaceDelimited::to_string("color", &"blue", &passthrough)?,
+            "color=blue"
+        );
+        assert_eq!(
+            PipeDelimited::to_string("color", &"blue", &passthrough)?,
+            "color=blue"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_seq() -> Result<(), QuerylizerError> {
+        let v = vec!["blue", "black", "brown"];
+        assert_eq!(
+            SpaceDelimited::to_string("color", &v, &passthrough)?,
+            "color=blue black brown"
+        );
+        assert_eq!(
+            SpaceDelimited::to_string("color", &v, &encode_query)?,
+            "color=blue%20black%20brown"
+        );
+        assert_eq!(
+            PipeDelimited::to_string("color", &v, &passthrough)?,
+            "color=blue|black|brown"
+        );
+        assert_eq!(
+            PipeDelimited::to_string("color", &v, &encode_query)?,
+            "color=blue|black|brown"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_tuple() -> Result<(), QuerylizerError> {
+        let t = ("blue", "black", "brown");
+        assert_eq!(
+            SpaceDelimited::to_string("color", &t, &encode_query)?,
+            "color=blue%20black%20brown"
+        );
+        assert_eq!(
+            PipeDelimited::to_string("color", &t, &passthrough)?,
+            "color=blue|black|brown"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_map() -> Result<(), QuerylizerError> {
+        let mut m = std::collections::BTreeMap::new();
+        m.insert("R", 100);
+        m.insert("G", 200);
+        m.insert("B", 150);
+        assert_eq!(
+            SpaceDelimited::to_string("color", &m, &encode_query)?,
+            "color=B%20150%20G%20200%20R%20100"
+        );
+        assert_eq!(
+            PipeDelimited::to_string("color", &m, &passthrough)?,
+            "color=B|150|G|200|R|100"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_struct() {
+        #[derive(Serialize)]
+        struct Test {
+            #[serde(rename = "R")]
+            r: u32,
+            #[serde(rename = "G")]
+            g: u32,
+            #[serde(rename = "B")]
+            b: u32,
+        }
+
+        let test = Test {
+            r: 100,
+            g: 200,
+            b: 150,
+        };
+        assert_eq!(
+            SpaceDelimited::to_string("color", &test, &encode_query).unwrap(),
+            "color=R%20100%20G%20200%20B%20150"
+        );
+        assert_eq!(
+            PipeDelimited::to_string("color", &test, &passthrough).unwrap(),
+            "color=R|100|G|200|B|150"
+        );
+    }
+
+    #[test]
+    fn test_encoded_values() -> Result<(), QuerylizerError> {
+        let v = vec!["a b", "c|d"];
+        assert_eq!(
+            SpaceDelimited::to_string("color", &v, &encode_query)?,
+            "color=a%20b%20c%7Cd"
+        );
+        assert_eq!(
+            PipeDelimited::to_string("color", &v, &encode_query)?,
+            "color=a%20b|c%7Cd"
+        );
+        assert_eq!(
+            PipeDelimited::to_string("c", &["a|b"], &encode_query)?,
+            "c=a%7Cb"
+        );
+        assert_eq!(
+            PipeDelimited::to_string("c", &["a", "b"], &encode_query)?,
+            "c=a|b"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_unsupported_nesting() {
+        let v = vec![vec!["blue"], vec!["black"]];
+        assert!(matches!(
+            SpaceDelimited::to_string("color", &v, &passthrough),
+            Err(QuerylizerError::UnsupportedNesting(_))
+        ));
+        assert!(matches!(
+            PipeDelimited::to_string("color", &v, &passthrough),
+            Err(QuerylizerError::UnsupportedNesting(_))
+        ));
+    }
+
+    #[test]
+    fn test_empty_containers() -> Result<(), QuerylizerError> {
+        let v: Vec<u32> = Vec::new();
+        let options = Options::new().empty_containers(EmptyPolicy::Empty);
+        assert_eq!(
+            SpaceDelimited::to_string_with_options("color", &v, &passthrough, &options)?,
+            "color="
+        );
+        let options = Options::new().empty_containers(EmptyPolicy::Omit);
+        assert_eq!(
+            PipeDelimited::to_string_with_options("color", &v, &passthrough, &options)?,
+            ""
+        );
+        Ok(())
+    }
+}
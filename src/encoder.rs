@@ -0,0 +1,222 @@
+// Copyright 2022 Jonathan Giddy
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{EncodingFn, QuerylizerError, Sink};
+
+/// Encode strings directly into a [`Sink`].
+///
+/// All serializers take an `Encoder`. Every [`EncodingFn`], such as
+/// [`encode_query`](crate::encode_query), is an `Encoder` that writes each chunk returned by the
+/// function. [`PercentEncoder`] writes without the iterator, and is faster for the standard
+/// encodings.
+pub trait Encoder {
+    /// Write the encoded form of `s` to the output.
+    fn encode<W>(&self, s: &str, output: &mut W) -> Result<(), QuerylizerError>
+    where
+        W: Sink + ?Sized;
+}
+
+impl<F> Encoder for F
+where
+    F: for<'a> EncodingFn<'a>,
+{
+    fn encode<W>(&self, s: &str, output: &mut W) -> Result<(), QuerylizerError>
+    where
+        W: Sink + ?Sized,
+    {
+        for chunk in self.call(s) {
+            output.write_str(chunk)?;
+        }
+        Ok(())
+    }
+}
+
+// Mark ASCII bytes from `from` to `to` inclusive as written without escaping.
+const fn allow_range(mut table: [bool; 128], from: u8, to: u8) -> [bool; 128] {
+    let mut b = from;
+    while b <= to {
+        table[b as usize] = true;
+        b += 1;
+    }
+    table
+}
+
+const fn allow(mut table: [bool; 128], bytes: &[u8]) -> [bool; 128] {
+    let mut i = 0;
+    while i < bytes.len() {
+        table[bytes[i] as usize] = true;
+        i += 1;
+    }
+    table
+}
+
+const fn deny(mut table: [bool; 128], bytes: &[u8]) -> [bool; 128] {
+    let mut i = 0;
+    while i < bytes.len() {
+        table[bytes[i] as usize] = false;
+        i += 1;
+    }
+    table
+}
+
+const ALPHANUMERIC: [bool; 128] = allow_range(
+    allow_range(allow_range([false; 128], b'0', b'9'), b'A', b'Z'),
+    b'a',
+    b'z',
+);
+
+const UNRESERVED: [bool; 128] = allow(ALPHANUMERIC, b"-._~");
+
+/// A percent-encoder that looks up each byte in a table and copies runs of unescaped bytes in
+/// bulk.
+///
+/// The constants match the encoding functions of the same names, and escape all non-ASCII
+/// bytes.
+///
+/// # Example
+///
+/// ```
+/// use querylizer::{Form, PercentEncoder};
+/// let s = Form::to_string("q", "a b/c", true, &PercentEncoder::QUERY)?;
+/// assert_eq!(s, "q=a%20b%2Fc");
+/// # Ok::<(), querylizer::QuerylizerError>(())
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PercentEncoder {
+    // ASCII bytes that are written without escaping
+    unescaped: [bool; 128],
+}
+
+impl PercentEncoder {
+    /// Encode like [`encode_path`](crate::encode_path).
+    pub const PATH: PercentEncoder = PercentEncoder {
+        unescaped: allow(UNRESERVED, b"!$&'()*+,;=:@"),
+    };
+
+    /// Encode like [`encode_query`](crate::encode_query).
+    pub const QUERY: PercentEncoder = PercentEncoder {
+        unescaped: UNRESERVED,
+    };
+
+    /// Encode like [`encode_query_allow_reserved`](crate::encode_query_allow_reserved).
+    pub const QUERY_ALLOW_RESERVED: PercentEncoder = PercentEncoder {
+        unescaped: allow(UNRESERVED, b":/?#[]@!$&'()*+,;="),
+    };
+
+    /// Encode like [`encode_www_form_urlencoded`](crate::encode_www_form_urlencoded).
+    pub const WWW_FORM_URLENCODED: PercentEncoder = PercentEncoder {
+        unescaped: allow(ALPHANUMERIC, b"*-._"),
+    };
+
+    /// Encode like [`encode_cookie`](crate::encode_cookie).
+    pub const COOKIE: PercentEncoder = PercentEncoder {
+        unescaped: deny(allow_range([false; 128], b'!', b'~'), b"\"%,;=\\"),
+    };
+
+    fn is_unescaped(&self, b: u8) -> bool {
+        b < 128 && self.unescaped[usize::from(b)]
+    }
+}
+
+impl Encoder for PercentEncoder {
+    fn encode<W>(&self, s: &str, output: &mut W) -> Result<(), QuerylizerError>
+    where
+        W: Sink + ?Sized,
+    {
+        let bytes = s.as_bytes();
+        let mut start = 0;
+        for (i, &b) in bytes.iter().enumerate() {
+            if !self.is_unescaped(b) {
+                // Runs of unescaped bytes are ASCII, so `start` and `i` are on char boundaries
+                if start < i {
+                    output.write_str(&s[start..i])?;
+                }
+                output.write_str(percent_encoding::percent_encode_byte(b))?;
+                start = i + 1;
+            }
+        }
+        if start < bytes.len() {
+            output.write_str(&s[start..])?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        encode_cookie, encode_path, encode_query, encode_query_allow_reserved,
+        encode_www_form_urlencoded, passthrough, EncodingFn, Form, QuerylizerError,
+    };
+
+    use super::{Encoder, PercentEncoder};
+
+    fn assert_same<F>(fast: &PercentEncoder, encoder: &F)
+    where
+        F: for<'a> EncodingFn<'a>,
+    {
+        let mut input = (0..128u8).map(char::from).collect::<String>();
+        input.push_str("é€😀 end");
+        let mut expected = String::new();
+        encoder.encode(&input, &mut expected).unwrap();
+        let mut output = String::new();
+        fast.encode(&input, &mut output).unwrap();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_matches_encoding_fn() {
+        assert_same(&PercentEncoder::PATH, &encode_path);
+        assert_same(&PercentEncoder::QUERY, &encode_query);
+        assert_same(
+            &PercentEncoder::QUERY_ALLOW_RESERVED,
+            &encode_query_allow_reserved,
+        );
+        assert_same(
+            &PercentEncoder::WWW_FORM_URLENCODED,
+            &encode_www_form_urlencoded,
+        );
+        assert_same(&PercentEncoder::COOKIE, &encode_cookie);
+    }
+
+    #[test]
+    fn test_runs() -> Result<(), QuerylizerError> {
+        let cases = [
+            ("", ""),
+            ("abc", "abc"),
+            (" ", "%20"),
+            ("a b", "a%20b"),
+            (" ab ", "%20ab%20"),
+            ("é", "%C3%A9"),
+        ];
+        for (input, expected) in cases {
+            let mut output = String::new();
+            PercentEncoder::QUERY.encode(input, &mut output)?;
+            assert_eq!(output, expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_encoding_fn_adapter() -> Result<(), QuerylizerError> {
+        let mut output = String::new();
+        passthrough.encode("a b", &mut output)?;
+        assert_eq!(output, "a b");
+        assert_eq!(
+            Form::to_string("a b", &["c d", "e"], false, &encode_query)?,
+            Form::to_string("a b", &["c d", "e"], false, &PercentEncoder::QUERY)?
+        );
+        Ok(())
+    }
+}
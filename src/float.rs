@@ -0,0 +1,124 @@
+// Copyright 2022 Jonathan Giddy
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::Display;
+
+use crate::{FloatFormat, Options, QuerylizerError, Unsupported};
+
+pub(crate) trait Float: dtoa::Float + Display {
+    fn is_finite(self) -> bool;
+    fn is_nan(self) -> bool;
+}
+
+impl Float for f32 {
+    fn is_finite(self) -> bool {
+        f32::is_finite(self)
+    }
+    fn is_nan(self) -> bool {
+        f32::is_nan(self)
+    }
+}
+
+impl Float for f64 {
+    fn is_finite(self) -> bool {
+        f64::is_finite(self)
+    }
+    fn is_nan(self) -> bool {
+        f64::is_nan(self)
+    }
+}
+
+/// Format a float using the float options, and pass the text to `write`.
+pub(crate) fn write_float<V, W>(v: V, options: &Options, write: W) -> Result<(), QuerylizerError>
+where
+    V: Float,
+    W: FnOnce(&str) -> Result<(), QuerylizerError>,
+{
+    if options.finite_floats && !v.is_finite() {
+        return Err(QuerylizerError::NonFiniteFloat(Unsupported {
+            rejected: if v.is_nan() { "NaN" } else { "infinity" },
+            ..Unsupported::default()
+        }));
+    }
+    match options.float_format {
+        FloatFormat::Shortest => {
+            let mut buffer = dtoa::Buffer::new();
+            write(buffer.format(v))
+        }
+        FloatFormat::Fixed(precision) => write(&format!("{:.*}", precision, v)),
+        FloatFormat::Decimal => write(&v.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FloatFormat, Options, QuerylizerError};
+
+    use super::{write_float, Float};
+
+    fn format<V: Float>(v: V, options: &Options) -> Result<String, QuerylizerError> {
+        let mut output = String::new();
+        write_float(v, options, |s| {
+            output.push_str(s);
+            Ok(())
+        })?;
+        Ok(output)
+    }
+
+    #[test]
+    fn test_shortest() -> Result<(), QuerylizerError> {
+        let options = Options::new();
+        assert_eq!(format(1.5f64, &options)?, "1.5");
+        assert_eq!(format(0.1f32, &options)?, "0.1");
+        assert_eq!(format(1e20f64, &options)?, "100000000000000000000.0");
+        assert_eq!(format(1e21f64, &options)?, "1e21");
+        Ok(())
+    }
+
+    #[test]
+    fn test_fixed() -> Result<(), QuerylizerError> {
+        let options = Options::new().float_format(FloatFormat::Fixed(2));
+        assert_eq!(format(1.5f64, &options)?, "1.50");
+        assert_eq!(format(-0.125f32, &options)?, "-0.12");
+        assert_eq!(format(1e20f64, &options)?, "100000000000000000000.00");
+        Ok(())
+    }
+
+    #[test]
+    fn test_decimal() -> Result<(), QuerylizerError> {
+        let options = Options::new().float_format(FloatFormat::Decimal);
+        assert_eq!(format(1.5f64, &options)?, "1.5");
+        assert_eq!(format(0.1f32, &options)?, "0.1");
+        assert_eq!(format(1e21f64, &options)?, "1000000000000000000000");
+        assert_eq!(format(1e-7f64, &options)?, "0.0000001");
+        Ok(())
+    }
+
+    #[test]
+    fn test_non_finite() -> Result<(), QuerylizerError> {
+        let options = Options::new();
+        assert!(matches!(
+            format(f64::NAN, &options),
+            Err(QuerylizerError::NonFiniteFloat(u)) if u.rejected == "NaN"
+        ));
+        assert!(matches!(
+            format(f32::NEG_INFINITY, &options),
+            Err(QuerylizerError::NonFiniteFloat(u)) if u.rejected == "infinity"
+        ));
+        let options = Options::new().finite_floats(false);
+        assert_eq!(format(f64::NAN, &options)?, "NaN");
+        assert_eq!(format(f32::NEG_INFINITY, &options)?, "-inf");
+        Ok(())
+    }
+}
@@ -14,7 +14,11 @@
 
 use serde::{ser, Serialize, Serializer};
 
-use crate::{EncodingFn, QuerylizerError};
+use crate::builder::reserve_methods;
+use crate::bytes::encode_bytes;
+use crate::float::write_float;
+use crate::probe::{as_bool, is_none, key_name};
+use crate::{BoolFormat, Builder, Config, Encoder, Options, QuerylizerError, Simple, Sink};
 
 enum State {
     // Top-level outside any container
@@ -25,33 +29,69 @@ enum State {
     InnerNext,
 }
 
+// Separator between items when `explode` is `false`
+pub(crate) enum Delimiter {
+    Comma,
+    Space,
+    Pipe,
+}
+
+impl Delimiter {
+    // The name of the style that uses this delimiter, for errors
+    fn style(&self) -> &'static str {
+        match self {
+            Delimiter::Comma => "form",
+            Delimiter::Space => "spaceDelimited",
+            Delimiter::Pipe => "pipeDelimited",
+        }
+    }
+}
+
 /// Serialize a value into an OpenAPI `form` query parameter.
-pub struct Form<'s, F>
+pub struct Form<'s, F, W = String>
 where
-    F: for<'a> EncodingFn<'a>,
+    F: Encoder,
+    W: Sink + ?Sized,
 {
-    output: &'s mut String,
+    output: &'s mut W,
     name: &'s str,
     explode: bool,
     encoder: &'s F,
+    options: Options,
     state: State,
+    // Index of the next item in a sequence
+    index: usize,
+    delimiter: Delimiter,
+    // Separator between exploded items
+    separator: &'static str,
+    // Encoded key of the next map value
+    key: String,
+    // An element of the current container was left out
+    omitted: bool,
+    // Writing cookies, which need a value and whose names must be tokens
+    cookie: bool,
 }
 
 impl<'s, F> Form<'s, F>
 where
-    F: for<'a> EncodingFn<'a>,
+    F: Encoder,
 {
+    /// Create a builder for a `form` serializer, with `explode` set to `true`.
+    pub fn builder(encoder: &'s F) -> Builder<'s, Self, F> {
+        Builder::new(encoder, true)
+    }
+
     /// Serialize a `form` value into a new string to be used for web requests.
     ///
     /// If `explode` is `false`:
     /// - sequences and tuples use the name once and items are comma-separated (`name=item1,item2`)
     /// - maps and structs use the name once and keys and values are comma separated
-    /// (`name=key1,value1,key2,value2`).
+    ///   (`name=key1,value1,key2,value2`).
     ///
     /// If `explode` is `true`:
     /// - sequences repeat the name and separate with `&` (`name=item1&name=item2`)
     /// - maps and structs do not use the name and keys and values are separated with `=`
-    /// (`key1=value1&key2=value2`)
+    ///   (`key1=value1&key2=value2`)
     ///
     /// # Example
     ///
@@ -72,32 +112,60 @@ where
         explode: bool,
         encoder: &F,
     ) -> Result<String, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        Self::to_string_with_options(name, value, explode, encoder, &Options::default())
+    }
+
+    /// Serialize a `form` value into a new string, using the provided options.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use querylizer::{encode_query, Form, NullPolicy, Options};
+    /// #[derive(serde::Serialize)]
+    /// struct A {
+    ///     a: Option<i32>,
+    ///     b: Option<i32>,
+    /// }
+    /// let a = A { a: None, b: Some(2) };
+    /// let options = Options::new().null_fields(NullPolicy::Empty);
+    /// let s = Form::to_string_with_options("value", &a, true, &encode_query, &options).unwrap();
+    /// assert_eq!(s, "a=&b=2".to_owned());
+    /// ```
+    pub fn to_string_with_options<T>(
+        name: &str,
+        value: &T,
+        explode: bool,
+        encoder: &F,
+        options: &Options,
+    ) -> Result<String, QuerylizerError>
     where
         T: ?Sized + Serialize,
     {
         let mut output = String::new();
-        let mut serializer = Form {
-            output: &mut output,
-            name,
-            explode,
-            encoder,
-            state: State::Outer,
-        };
-        value.serialize(&mut serializer)?;
+        Self::extend_with_options(&mut output, name, value, explode, encoder, options)?;
         Ok(output)
     }
+}
 
-    /// Append a `form` value onto an existing string to be used for web requests.
+impl<'s, F, W> Form<'s, F, W>
+where
+    F: Encoder,
+    W: Sink + ?Sized,
+{
+    /// Append a `form` value onto an existing string or other [`Sink`] to be used for web requests.
     ///
     /// If `explode` is `false`:
     /// - sequences and tuples use the name once and items are comma-separated (`name=item1,item2`)
     /// - maps and structs use the name once and keys and values are comma separated
-    /// (`name=key1,value1,key2,value2`).
+    ///   (`name=key1,value1,key2,value2`).
     ///
     /// If `explode` is `true`:
     /// - sequences repeat the name and separate with `&` (`name=item1&name=item2`)
     /// - maps and structs do not use the name and keys and values are separated with `=`
-    /// (`key1=value1&key2=value2`)
+    ///   (`key1=value1&key2=value2`)
     ///
     /// # Example
     ///
@@ -114,30 +182,248 @@ where
     /// assert_eq!(s, "https://example.com/v1/?a=12&b=%23hello".to_owned());
     /// ```
     pub fn extend<T>(
-        output: &mut String,
+        output: &mut W,
+        name: &str,
+        value: &T,
+        explode: bool,
+        encoder: &F,
+    ) -> Result<(), QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        Self::extend_with_options(output, name, value, explode, encoder, &Options::default())
+    }
+
+    /// Append a `form` value onto an existing string, using the provided options.
+    pub fn extend_with_options<T>(
+        output: &mut W,
+        name: &str,
+        value: &T,
+        explode: bool,
+        encoder: &F,
+        options: &Options,
+    ) -> Result<(), QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        let serializer = Form {
+            output,
+            name,
+            explode,
+            encoder,
+            options: *options,
+            state: State::Outer,
+            index: 0,
+            delimiter: Delimiter::Comma,
+            separator: "&",
+            key: String::new(),
+            omitted: false,
+            cookie: false,
+        };
+        serializer.serialize_parameter(value)
+    }
+
+    // Append a `form` cookie parameter, where exploded items are separate cookies. Presence-only
+    // bools are rejected, since a cookie needs a value, as are names that are not tokens once
+    // encoded.
+    pub(crate) fn extend_cookie<T>(
+        output: &mut W,
         name: &str,
         value: &T,
         explode: bool,
         encoder: &F,
+        options: &Options,
     ) -> Result<(), QuerylizerError>
     where
         T: ?Sized + Serialize,
     {
-        let mut serializer = Form {
+        let serializer = Form {
             output,
             name,
             explode,
             encoder,
+            options: *options,
+            state: State::Outer,
+            index: 0,
+            delimiter: Delimiter::Comma,
+            separator: "; ",
+            key: String::new(),
+            omitted: false,
+            cookie: true,
+        };
+        serializer.serialize_parameter(value)
+    }
+
+    pub(crate) fn extend_delimited<T>(
+        output: &mut W,
+        name: &str,
+        value: &T,
+        encoder: &F,
+        delimiter: Delimiter,
+        options: &Options,
+    ) -> Result<(), QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        let serializer = Form {
+            output,
+            name,
+            explode: false,
+            encoder,
+            options: *options,
             state: State::Outer,
+            index: 0,
+            delimiter,
+            separator: "&",
+            key: String::new(),
+            omitted: false,
+            cookie: false,
         };
-        value.serialize(&mut serializer)?;
+        serializer.serialize_parameter(value)
+    }
+
+    // Serialize the whole parameter value, adding the style and name to any error.
+    fn serialize_parameter<T>(mut self, value: &T) -> Result<(), QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        let style = self.delimiter.style();
+        let name = self.name;
+        value
+            .serialize(&mut self)
+            .map_err(|e| e.in_parameter(style, name))
+    }
+
+    // Finish an empty sequence, map or struct. An empty `form` value is the name with an empty
+    // value. A container with every element left out is left out too.
+    fn end_empty(&mut self, rejected: &'static str) -> Result<(), QuerylizerError> {
+        self.state = State::Outer;
+        if std::mem::take(&mut self.omitted) {
+            return Ok(());
+        }
+        if self.options.empty_containers.write_empty(rejected)? {
+            self.write_name(self.name, self.cookie)?;
+            self.output.write_char('=')?;
+        }
         Ok(())
     }
+
+    fn push_delimiter(&mut self) -> Result<(), QuerylizerError> {
+        match self.delimiter {
+            Delimiter::Comma => self.output.write_char(','),
+            Delimiter::Space => self.encoder.encode(" ", self.output),
+            Delimiter::Pipe => self.output.write_char('|'),
+        }
+    }
+
+    // Write the separator before the key of a map entry or struct field.
+    fn begin_entry(&mut self) -> Result<(), QuerylizerError> {
+        match self.state {
+            State::Outer => unreachable!(),
+            State::InnerFirst => {
+                self.state = State::InnerNext;
+                if !self.explode {
+                    self.write_name(self.name, self.cookie)?;
+                    self.output.write_char('=')?;
+                }
+                Ok(())
+            }
+            State::InnerNext => {
+                if self.explode {
+                    self.output.write_str(self.separator)
+                } else {
+                    self.push_delimiter()
+                }
+            }
+        }
+    }
+
+    // Write an encoded name, which must be a token if it is a cookie name.
+    fn write_name(&mut self, name: &str, cookie: bool) -> Result<(), QuerylizerError> {
+        if !cookie {
+            return self.encoder.encode(name, self.output);
+        }
+        let mut encoded = String::new();
+        self.encoder.encode(name, &mut encoded)?;
+        check_cookie_name(&encoded)?;
+        self.output.write_str(&encoded)
+    }
+
+    // Write the separator between a key and its value.
+    fn assign(&mut self) -> Result<(), QuerylizerError> {
+        if self.explode {
+            self.output.write_char('=')
+        } else {
+            self.push_delimiter()
+        }
+    }
+
+    // The value of a presence-only flag in an exploded map or struct, which is written as its key
+    // alone if `true`, and left out with its separator if `false`.
+    fn presence_flag<T>(&self, value: &T) -> Option<bool>
+    where
+        T: ?Sized + Serialize,
+    {
+        if !self.cookie && self.explode && self.options.bool_format == BoolFormat::Presence {
+            as_bool(value)
+        } else {
+            None
+        }
+    }
+}
+
+impl<'s, F> Builder<'s, Form<'s, F>, F>
+where
+    F: Encoder,
+{
+    /// Set the parameter name.
+    pub fn name(mut self, name: &'s str) -> Self {
+        self.config.name = name;
+        self
+    }
+
+    /// Set whether to explode sequences, maps and structs.
+    pub fn explode(mut self, explode: bool) -> Self {
+        self.config.explode = explode;
+        self
+    }
 }
 
-impl<'a, 's, F> Serializer for &'a mut Form<'s, F>
+impl<'s, F> Config<'s, Form<'s, F>, F>
 where
-    F: for<'b> EncodingFn<'b>,
+    F: Encoder,
+{
+    /// Serialize a `form` value into a new string.
+    pub fn to_string<T>(&self, value: &T) -> Result<String, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        Form::to_string_with_options(self.name, value, self.explode, self.encoder, &self.options)
+    }
+
+    /// Append a `form` value onto an existing string or other sink.
+    pub fn extend<W, T>(&self, output: &mut W, value: &T) -> Result<(), QuerylizerError>
+    where
+        W: Sink + ?Sized,
+        T: ?Sized + Serialize,
+    {
+        Form::extend_with_options(
+            output,
+            self.name,
+            value,
+            self.explode,
+            self.encoder,
+            &self.options,
+        )
+    }
+
+    reserve_methods!("a `form` value");
+}
+
+impl<'a, 's, F, W> Serializer for &'a mut Form<'s, F, W>
+where
+    F: Encoder,
+    W: Sink + ?Sized,
 {
     type Ok = ();
 
@@ -157,7 +443,16 @@ where
     type SerializeStructVariant = Self;
 
     fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
-        self.serialize_str(if v { "true" } else { "false" })
+        match (self.options.bool_format.text(v), &self.state) {
+            (Some(s), _) => self.serialize_str(s),
+            (None, State::Outer) if !self.cookie => {
+                if v {
+                    self.write_name(self.name, self.cookie)?;
+                }
+                Ok(())
+            }
+            (None, _) => Err(QuerylizerError::unsupported_value("presence-only bool")),
+        }
     }
 
     fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
@@ -199,12 +494,12 @@ where
     }
 
     fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
-        let mut buffer = dtoa::Buffer::new();
-        self.serialize_str(buffer.format(v))
+        let options = self.options;
+        write_float(v, &options, |s| self.serialize_str(s))
     }
     fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
-        let mut buffer = dtoa::Buffer::new();
-        self.serialize_str(buffer.format(v))
+        let options = self.options;
+        write_float(v, &options, |s| self.serialize_str(s))
     }
 
     fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
@@ -216,14 +511,17 @@ where
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
         if let State::Outer = self.state {
-            self.output.extend(self.encoder.call(self.name));
-            self.output.push('=');
+            self.write_name(self.name, self.cookie)?;
+            self.output.write_char('=')?;
         }
-        self.output.extend(self.encoder.call(v));
+        self.encoder.encode(v, self.output)?;
         Ok(())
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        if let Some(s) = encode_bytes(v, self.options.bytes_encoding) {
+            return self.serialize_str(&s);
+        }
         use ser::SerializeSeq;
         let mut seq_serializer = self.serialize_seq(Some(v.len()))?;
         for byte in v {
@@ -235,9 +533,13 @@ where
 
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
         if let State::Outer = self.state {
-            self.serialize_str("")
+            if self.options.null_parameters.write_none()? {
+                self.serialize_str("")
+            } else {
+                Ok(())
+            }
         } else {
-            Err(QuerylizerError::UnsupportedNesting)
+            Err(QuerylizerError::unsupported_nesting("none"))
         }
     }
 
@@ -245,36 +547,32 @@ where
     where
         T: ?Sized + Serialize,
     {
-        if let State::Outer = self.state {
-            value.serialize(self)
-        } else {
-            Err(QuerylizerError::UnsupportedNesting)
-        }
+        value.serialize(self)
     }
 
     fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
         if let State::Outer = self.state {
-            self.serialize_str("")
+            if self.options.null_parameters.write_none()? {
+                self.serialize_str("")
+            } else {
+                Ok(())
+            }
         } else {
-            Err(QuerylizerError::UnsupportedNesting)
+            Err(QuerylizerError::unsupported_nesting("unit"))
         }
     }
 
     fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
-        Err(QuerylizerError::UnsupportedNesting)
+        Err(QuerylizerError::unsupported_value("unit struct"))
     }
 
     fn serialize_unit_variant(
         self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
-        if let State::Outer = self.state {
-            self.serialize_str("")
-        } else {
-            Err(QuerylizerError::UnsupportedNesting)
-        }
+        self.serialize_str(variant)
     }
 
     fn serialize_newtype_struct<T>(
@@ -307,7 +605,7 @@ where
                 self.state = State::InnerFirst;
                 Ok(self)
             }
-            _ => Err(QuerylizerError::UnsupportedNesting),
+            _ => Err(QuerylizerError::unsupported_nesting("seq")),
         }
     }
 
@@ -317,7 +615,7 @@ where
                 self.state = State::InnerFirst;
                 Ok(self)
             }
-            _ => Err(QuerylizerError::UnsupportedNesting),
+            _ => Err(QuerylizerError::unsupported_nesting("tuple")),
         }
     }
 
@@ -331,7 +629,7 @@ where
                 self.state = State::InnerFirst;
                 Ok(self)
             }
-            _ => Err(QuerylizerError::UnsupportedNesting),
+            _ => Err(QuerylizerError::unsupported_nesting("tuple struct")),
         }
     }
 
@@ -347,7 +645,7 @@ where
                 self.state = State::InnerFirst;
                 Ok(self)
             }
-            _ => Err(QuerylizerError::UnsupportedNesting),
+            _ => Err(QuerylizerError::unsupported_nesting("tuple variant")),
         }
     }
 
@@ -357,7 +655,7 @@ where
                 self.state = State::InnerFirst;
                 Ok(self)
             }
-            _ => Err(QuerylizerError::UnsupportedNesting),
+            _ => Err(QuerylizerError::unsupported_nesting("map")),
         }
     }
 
@@ -371,7 +669,7 @@ where
                 self.state = State::InnerFirst;
                 Ok(self)
             }
-            _ => Err(QuerylizerError::UnsupportedNesting),
+            _ => Err(QuerylizerError::unsupported_nesting("struct")),
         }
     }
 
@@ -387,16 +685,17 @@ where
                 self.state = State::InnerFirst;
                 Ok(self)
             }
-            _ => Err(QuerylizerError::UnsupportedNesting),
+            _ => Err(QuerylizerError::unsupported_nesting("struct variant")),
         }
     }
 }
 
 macro_rules! seq_serializer {
     ($trait:ty, $serialize:ident) => {
-        impl<'a, 's, F> $trait for &'a mut Form<'s, F>
+        impl<'a, 's, F, W> $trait for &'a mut Form<'s, F, W>
         where
-            F: for<'b> EncodingFn<'b>,
+            F: Encoder,
+            W: Sink + ?Sized,
         {
             type Ok = ();
             type Error = QuerylizerError;
@@ -409,28 +708,31 @@ macro_rules! seq_serializer {
                     State::Outer => unreachable!(),
                     State::InnerFirst => {
                         self.state = State::InnerNext;
-                        self.output.extend(self.encoder.call(&self.name));
-                        self.output.push('=');
+                        self.write_name(self.name, self.cookie)?;
+                        self.output.write_char('=')?;
                     }
                     State::InnerNext => {
                         if self.explode {
-                            self.output.push('&');
-                            self.output.extend(self.encoder.call(&self.name));
-                            self.output.push('=');
+                            self.output.write_str(self.separator)?;
+                            self.write_name(self.name, self.cookie)?;
+                            self.output.write_char('=')?;
                         } else {
-                            self.output.push(',');
+                            self.push_delimiter()?;
                         }
                     }
                 }
-                value.serialize(&mut **self)
+                let index = self.index;
+                self.index += 1;
+                value.serialize(&mut **self).map_err(|e| e.at_index(index))
             }
 
             fn end(self) -> Result<(), Self::Error> {
                 match self.state {
                     State::Outer => unreachable!(),
-                    State::InnerFirst => Err(QuerylizerError::UnsupportedValue),
+                    State::InnerFirst => self.end_empty("empty sequence"),
                     State::InnerNext => {
                         self.state = State::Outer;
+                        self.index = 0;
                         Ok(())
                     }
                 }
@@ -444,9 +746,10 @@ seq_serializer!(ser::SerializeTuple, serialize_element);
 seq_serializer!(ser::SerializeTupleStruct, serialize_field);
 seq_serializer!(ser::SerializeTupleVariant, serialize_field);
 
-impl<'a, 's, F> ser::SerializeMap for &'a mut Form<'s, F>
+impl<'a, 's, F, W> ser::SerializeMap for &'a mut Form<'s, F, W>
 where
-    F: for<'b> EncodingFn<'b>,
+    F: Encoder,
+    W: Sink + ?Sized,
 {
     type Ok = ();
     type Error = QuerylizerError;
@@ -455,39 +758,55 @@ where
     where
         T: ?Sized + Serialize,
     {
-        match self.state {
-            State::Outer => unreachable!(),
-            State::InnerFirst => {
-                self.state = State::InnerNext;
-                if !self.explode {
-                    self.output.extend(self.encoder.call(self.name));
-                    self.output.push('=');
-                }
-            }
-            State::InnerNext => {
-                self.output.push(if self.explode { '&' } else { ',' });
-            }
-        }
-        key.serialize(&mut **self)
+        // Hold the key until the value shows whether the entry is left out
+        self.key.clear();
+        Simple::extend_with_options(&mut self.key, key, false, self.encoder, &self.options)
     }
 
     fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
         T: ?Sized + Serialize,
     {
-        match self.state {
-            State::Outer => unreachable!(),
-            _ => {
-                self.output.push(if self.explode { '=' } else { ',' });
-            }
+        let flag = self.presence_flag(value);
+        if flag == Some(false) {
+            self.omitted = true;
+            return Ok(());
+        }
+        let none = is_none(value);
+        if none && !self.options.null_fields.write_none()? {
+            self.omitted = true;
+            return Ok(());
+        }
+        if self.cookie && self.explode {
+            check_cookie_name(&self.key)?;
+        }
+        self.begin_entry()?;
+        self.output.write_str(&self.key)?;
+        if flag.is_some() {
+            return Ok(());
+        }
+        self.assign()?;
+        if none {
+            "".serialize(&mut **self)
+        } else {
+            value.serialize(&mut **self)
         }
-        value.serialize(&mut **self)
+    }
+
+    fn serialize_entry<K, V>(&mut self, key: &K, value: &V) -> Result<(), Self::Error>
+    where
+        K: ?Sized + Serialize,
+        V: ?Sized + Serialize,
+    {
+        self.serialize_key(key)?;
+        self.serialize_value(value)
+            .map_err(|e| e.at_key(&key_name(key)))
     }
 
     fn end(self) -> Result<(), Self::Error> {
         match self.state {
             State::Outer => unreachable!(),
-            State::InnerFirst => Err(QuerylizerError::UnsupportedValue),
+            State::InnerFirst => self.end_empty("empty map"),
             State::InnerNext => {
                 self.state = State::Outer;
                 Ok(())
@@ -498,9 +817,10 @@ where
 
 macro_rules! struct_serializer {
     ($trait:ty) => {
-        impl<'a, 's, F> $trait for &'a mut Form<'s, F>
+        impl<'a, 's, F, W> $trait for &'a mut Form<'s, F, W>
         where
-            F: for<'b> EncodingFn<'b>,
+            F: Encoder,
+            W: Sink + ?Sized,
         {
             type Ok = ();
             type Error = QuerylizerError;
@@ -513,33 +833,40 @@ macro_rules! struct_serializer {
             where
                 T: ?Sized + Serialize,
             {
-                match self.state {
-                    State::Outer => unreachable!(),
-                    State::InnerFirst => {
-                        self.state = State::InnerNext;
-                        if !self.explode {
-                            self.output.extend(self.encoder.call(&self.name));
-                            self.output.push('=');
-                        }
-                    }
-                    State::InnerNext => {
-                        self.output.push(if self.explode { '&' } else { ',' });
-                    }
+                let flag = self.presence_flag(value);
+                if flag == Some(false) {
+                    self.omitted = true;
+                    return Ok(());
                 }
-                key.serialize(&mut **self)?;
-                match self.state {
-                    State::Outer => unreachable!(),
-                    _ => {
-                        self.output.push(if self.explode { '=' } else { ',' });
-                    }
+                let none = is_none(value);
+                if none
+                    && !self
+                        .options
+                        .null_fields
+                        .write_none()
+                        .map_err(|e| e.at_key(key))?
+                {
+                    self.omitted = true;
+                    return Ok(());
+                }
+                self.begin_entry()?;
+                self.write_name(key, self.cookie && self.explode)
+                    .map_err(|e| e.at_key(key))?;
+                if flag.is_some() {
+                    return Ok(());
+                }
+                self.assign()?;
+                if none {
+                    "".serialize(&mut **self)
+                } else {
+                    value.serialize(&mut **self).map_err(|e| e.at_key(key))
                 }
-                value.serialize(&mut **self)
             }
 
             fn end(self) -> Result<(), Self::Error> {
                 match self.state {
                     State::Outer => unreachable!(),
-                    State::InnerFirst => Err(QuerylizerError::UnsupportedValue),
+                    State::InnerFirst => self.end_empty("empty struct"),
                     State::InnerNext => {
                         self.state = State::Outer;
                         Ok(())
@@ -553,11 +880,29 @@ macro_rules! struct_serializer {
 struct_serializer!(ser::SerializeStruct);
 struct_serializer!(ser::SerializeStructVariant);
 
+// A cookie name must be an RFC 6265 token: visible ASCII other than the HTTP separators.
+fn check_cookie_name(name: &str) -> Result<(), QuerylizerError> {
+    let token = !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_graphic() && !b"()<>@,;:\\\"/[]?={}".contains(&b));
+    if token {
+        Ok(())
+    } else {
+        Err(QuerylizerError::unsupported_value("invalid cookie name"))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use serde::Serialize;
 
-    use crate::{passthrough, QuerylizerError};
+    use crate::bytes::Bytes;
+    use crate::probe::Pairs;
+    use crate::{
+        encode_query, passthrough, BoolFormat, BytesEncoding, EmptyPolicy, FloatFormat, NullPolicy,
+        Options, QuerylizerError, Unsupported,
+    };
 
     use super::Form;
 
@@ -682,6 +1027,46 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_float_format() -> Result<(), QuerylizerError> {
+        let v = [1e20f64, 0.5];
+        let options = Options::new().float_format(FloatFormat::Fixed(1));
+        assert_eq!(
+            Form::to_string_with_options("v", &v, false, &passthrough, &options)?,
+            "v=100000000000000000000.0,0.5"
+        );
+        let options = Options::new().float_format(FloatFormat::Decimal);
+        assert_eq!(
+            Form::to_string_with_options("v", &v, false, &passthrough, &options)?,
+            "v=100000000000000000000,0.5"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_non_finite_float() -> Result<(), QuerylizerError> {
+        let v = [1.0, f64::INFINITY];
+        let options = Options::new().finite_floats(false);
+        assert_eq!(
+            Form::to_string_with_options("v", &v, true, &passthrough, &options)?,
+            "v=1.0&v=inf"
+        );
+        let error = Form::to_string("v", &v, true, &passthrough);
+        assert_eq!(
+            error,
+            Err(QuerylizerError::NonFiniteFloat(Unsupported {
+                style: "form",
+                path: "v[1]".to_owned(),
+                rejected: "infinity",
+            }))
+        );
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            "non-finite float not supported: infinity at `v[1]` in form style"
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_char() -> Result<(), QuerylizerError> {
         assert_eq!(
@@ -713,6 +1098,20 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_bytes_encoding() -> Result<(), QuerylizerError> {
+        let v = &Bytes(b"hi?");
+        let to_string = |bytes_encoding| {
+            let options = Options::new().bytes_encoding(bytes_encoding);
+            Form::to_string_with_options("v", v, false, &encode_query, &options)
+        };
+        assert_eq!(to_string(BytesEncoding::Decimal)?, "v=104,105,63");
+        assert_eq!(to_string(BytesEncoding::Base64)?, "v=aGk%2F");
+        assert_eq!(to_string(BytesEncoding::Base64Url)?, "v=aGk_");
+        assert_eq!(to_string(BytesEncoding::Hex)?, "v=68693f");
+        Ok(())
+    }
+
     #[test]
     fn test_none() -> Result<(), QuerylizerError> {
         assert_eq!(
@@ -722,6 +1121,47 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_none_parameter() -> Result<(), QuerylizerError> {
+        let options = Options::new().null_parameters(NullPolicy::Omit);
+        assert_eq!(
+            Form::to_string_with_options::<Option<u32>>(
+                "color",
+                &None,
+                true,
+                &passthrough,
+                &options
+            )?,
+            ""
+        );
+        let mut s = "a=1".to_owned();
+        Form::extend_with_options::<Option<u32>>(
+            &mut s,
+            "color",
+            &None,
+            true,
+            &passthrough,
+            &options,
+        )?;
+        assert_eq!(s, "a=1");
+        let options = Options::new().null_parameters(NullPolicy::Error);
+        assert_eq!(
+            Form::to_string_with_options::<Option<u32>>(
+                "color",
+                &None,
+                true,
+                &passthrough,
+                &options
+            ),
+            Err(QuerylizerError::UnsupportedValue(Unsupported {
+                style: "form",
+                path: "color".to_owned(),
+                rejected: "none",
+            }))
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_some() -> Result<(), QuerylizerError> {
         assert_eq!(
@@ -744,22 +1184,54 @@ mod tests {
     fn test_unit_struct() -> Result<(), QuerylizerError> {
         #[derive(Serialize)]
         struct T {}
-        assert_eq!(
+        assert!(matches!(
             Form::to_string("color", &T {}, false, &passthrough),
-            Err(QuerylizerError::UnsupportedValue)
-        );
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
+        #[derive(Serialize)]
+        struct U;
+        assert!(matches!(
+            Form::to_string("color", &U, false, &passthrough),
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
         Ok(())
     }
 
     #[test]
     fn test_unit_variant() -> Result<(), QuerylizerError> {
-        #[derive(Serialize)]
+        #[derive(Serialize, PartialEq, Eq, PartialOrd, Ord)]
         enum E {
             A,
+            #[serde(rename = "b")]
+            B,
+        }
+        #[derive(Serialize)]
+        struct S {
+            sort: E,
+            order: E,
         }
         assert_eq!(
             Form::to_string("color", &E::A, false, &passthrough)?,
-            "color="
+            "color=A"
+        );
+        assert_eq!(
+            Form::to_string("color", &E::B, false, &passthrough)?,
+            "color=b"
+        );
+        assert_eq!(
+            Form::to_string("color", &[E::A, E::B], true, &passthrough)?,
+            "color=A&color=b"
+        );
+        let mut m = std::collections::BTreeMap::new();
+        m.insert(E::A, E::B);
+        assert_eq!(Form::to_string("color", &m, true, &passthrough)?, "A=b");
+        let s = S {
+            sort: E::A,
+            order: E::B,
+        };
+        assert_eq!(
+            Form::to_string("color", &s, true, &passthrough)?,
+            "sort=A&order=b"
         );
         Ok(())
     }
@@ -943,9 +1415,334 @@ mod tests {
                 b: 150,
             },
         };
-        assert_eq!(
+        assert!(matches!(
             Form::to_string("color", &test, false, &passthrough),
-            Err(QuerylizerError::UnsupportedNesting)
+            Err(QuerylizerError::UnsupportedNesting(_))
+        ));
+    }
+
+    #[derive(Serialize)]
+    struct Optional {
+        a: Option<u32>,
+        b: Option<&'static str>,
+        c: u32,
+    }
+
+    #[test]
+    fn test_none_fields() -> Result<(), QuerylizerError> {
+        let test = Optional {
+            a: None,
+            b: Some("x"),
+            c: 3,
+        };
+        assert_eq!(
+            Form::to_string("color", &test, true, &passthrough)?,
+            "b=x&c=3"
+        );
+        assert_eq!(
+            Form::to_string("color", &test, false, &passthrough)?,
+            "color=b,x,c,3"
+        );
+        let test = Optional {
+            a: Some(1),
+            b: None,
+            c: 3,
+        };
+        assert_eq!(
+            Form::to_string("color", &test, true, &passthrough)?,
+            "a=1&c=3"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_none_fields_empty() -> Result<(), QuerylizerError> {
+        let test = Optional {
+            a: None,
+            b: Some("x"),
+            c: 3,
+        };
+        let options = Options::new().null_fields(NullPolicy::Empty);
+        assert_eq!(
+            Form::to_string_with_options("color", &test, true, &passthrough, &options)?,
+            "a=&b=x&c=3"
+        );
+        assert_eq!(
+            Form::to_string_with_options("color", &test, false, &passthrough, &options)?,
+            "color=a,,b,x,c,3"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_none_entries() -> Result<(), QuerylizerError> {
+        let mut m = std::collections::BTreeMap::new();
+        m.insert("a", None);
+        m.insert("b", Some(2));
+        assert_eq!(Form::to_string("color", &m, true, &passthrough)?, "b=2");
+        let options = Options::new().null_fields(NullPolicy::Empty);
+        assert_eq!(
+            Form::to_string_with_options("color", &m, true, &passthrough, &options)?,
+            "a=&b=2"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_none_values() -> Result<(), QuerylizerError> {
+        let pairs = Pairs(&[("a", None), ("b", Some(2))]);
+        assert_eq!(Form::to_string("color", &pairs, true, &passthrough)?, "b=2");
+        assert_eq!(
+            Form::to_string("color", &pairs, false, &passthrough)?,
+            "color=b,2"
+        );
+        let options = Options::new().null_fields(NullPolicy::Empty);
+        assert_eq!(
+            Form::to_string_with_options("color", &pairs, true, &passthrough, &options)?,
+            "a=&b=2"
         );
+        let options = Options::new().null_fields(NullPolicy::Error);
+        assert!(matches!(
+            Form::to_string_with_options("color", &pairs, true, &passthrough, &options),
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_none_fields_error() {
+        let test = Optional {
+            a: Some(1),
+            b: None,
+            c: 3,
+        };
+        let options = Options::new().null_fields(NullPolicy::Error);
+        assert_eq!(
+            Form::to_string_with_options("color", &test, true, &passthrough, &options),
+            Err(QuerylizerError::UnsupportedValue(Unsupported {
+                style: "form",
+                path: "color.b".to_owned(),
+                rejected: "none",
+            }))
+        );
+        let mut m = std::collections::BTreeMap::new();
+        m.insert("a", None::<u32>);
+        assert_eq!(
+            Form::to_string_with_options("color", &m, false, &passthrough, &options),
+            Err(QuerylizerError::UnsupportedValue(Unsupported {
+                style: "form",
+                path: "color.a".to_owned(),
+                rejected: "none",
+            }))
+        );
+    }
+
+    #[test]
+    fn test_none_fields_only() -> Result<(), QuerylizerError> {
+        // A struct with every field left out is left out too, rather than being empty
+        #[derive(Serialize)]
+        struct Test {
+            a: Option<u32>,
+        }
+        assert_eq!(
+            Form::to_string("color", &Test { a: None }, true, &passthrough)?,
+            ""
+        );
+        assert_eq!(
+            Form::to_string("color", &Test { a: None }, false, &passthrough)?,
+            ""
+        );
+        let mut m = std::collections::BTreeMap::new();
+        m.insert("a", false);
+        let options = Options::new().bool_format(BoolFormat::Presence);
+        assert_eq!(
+            Form::to_string_with_options("color", &m, true, &passthrough, &options)?,
+            ""
+        );
+        #[derive(Serialize)]
+        struct Empty {}
+        assert!(matches!(
+            Form::to_string("color", &Empty {}, true, &passthrough),
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_path() {
+        #[derive(Serialize)]
+        struct Test {
+            a: u32,
+            b: Vec<u32>,
+        }
+        let test = Test { a: 1, b: vec![2] };
+        assert_eq!(
+            Form::to_string("color", &test, true, &passthrough),
+            Err(QuerylizerError::UnsupportedNesting(Unsupported {
+                style: "form",
+                path: "color.b".to_owned(),
+                rejected: "seq",
+            }))
+        );
+        assert_eq!(
+            Form::to_string("color", &[vec![1], vec![2]], true, &passthrough),
+            Err(QuerylizerError::UnsupportedNesting(Unsupported {
+                style: "form",
+                path: "color[0]".to_owned(),
+                rejected: "seq",
+            }))
+        );
+        assert_eq!(
+            crate::SpaceDelimited::to_string("color", &Vec::<u32>::new(), &passthrough),
+            Err(QuerylizerError::UnsupportedValue(Unsupported {
+                style: "spaceDelimited",
+                path: "color".to_owned(),
+                rejected: "empty sequence",
+            }))
+        );
+    }
+
+    #[test]
+    fn test_empty_containers() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Empty {}
+        let v: Vec<u32> = Vec::new();
+        let options = Options::new().empty_containers(EmptyPolicy::Empty);
+        assert_eq!(
+            Form::to_string_with_options("color", &v, false, &passthrough, &options)?,
+            "color="
+        );
+        assert_eq!(
+            Form::to_string_with_options("color", &Empty {}, true, &passthrough, &options)?,
+            "color="
+        );
+        let options = Options::new().empty_containers(EmptyPolicy::Omit);
+        assert_eq!(
+            Form::to_string_with_options("color", &v, true, &passthrough, &options)?,
+            ""
+        );
+        assert_eq!(
+            Form::to_string("color", &Empty {}, true, &passthrough),
+            Err(QuerylizerError::UnsupportedValue(Unsupported {
+                style: "form",
+                path: "color".to_owned(),
+                rejected: "empty struct",
+            }))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_value() -> Result<(), QuerylizerError> {
+        use serde_json::json;
+
+        let to_string = |value| Form::to_string("v", &value, true, &passthrough);
+        assert_eq!(to_string(json!(null))?, "v=");
+        assert_eq!(to_string(json!(true))?, "v=true");
+        assert_eq!(to_string(json!(12))?, "v=12");
+        assert_eq!(to_string(json!(-12))?, "v=-12");
+        assert_eq!(to_string(json!(1.5))?, "v=1.5");
+        assert_eq!(to_string(json!("a b"))?, "v=a b");
+        assert_eq!(to_string(json!([1, "a", false]))?, "v=1&v=a&v=false");
+        assert_eq!(to_string(json!({"a": 1, "b": null, "c": "x"}))?, "a=1&c=x");
+        assert!(matches!(
+            to_string(json!({"a": {"b": 1}})),
+            Err(QuerylizerError::UnsupportedNesting(_))
+        ));
+        let options = Options::new()
+            .null_fields(NullPolicy::Empty)
+            .null_parameters(NullPolicy::Omit);
+        assert_eq!(
+            Form::to_string_with_options(
+                "v",
+                &json!({"a": 1, "b": null}),
+                false,
+                &passthrough,
+                &options
+            )?,
+            "v=a,1,b,"
+        );
+        assert_eq!(
+            Form::to_string_with_options("v", &json!(null), false, &passthrough, &options)?,
+            ""
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_bool_format() -> Result<(), QuerylizerError> {
+        let options = Options::new().bool_format(BoolFormat::OneZero);
+        assert_eq!(
+            Form::to_string_with_options("v", &[true, false], false, &passthrough, &options)?,
+            "v=1,0"
+        );
+        let options = Options::new().bool_format(BoolFormat::YesNo);
+        assert_eq!(
+            Form::to_string_with_options("v", &[true, false], true, &passthrough, &options)?,
+            "v=yes&v=no"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_bool_presence() -> Result<(), QuerylizerError> {
+        let options = Options::new().bool_format(BoolFormat::Presence);
+        assert_eq!(
+            Form::to_string_with_options("a b", &true, true, &encode_query, &options)?,
+            "a%20b"
+        );
+        assert_eq!(
+            Form::to_string_with_options("v", &false, true, &passthrough, &options)?,
+            ""
+        );
+        assert_eq!(
+            Form::to_string_with_options("v", &Some(true), true, &passthrough, &options)?,
+            "v"
+        );
+        let error = Form::to_string_with_options("v", &[true], true, &passthrough, &options);
+        assert_eq!(
+            error,
+            Err(QuerylizerError::UnsupportedValue(Unsupported {
+                style: "form",
+                path: "v[0]".to_owned(),
+                rejected: "presence-only bool",
+            }))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_bool_presence_fields() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Flags {
+            debug: bool,
+            id: u32,
+            quiet: bool,
+            verbose: Option<bool>,
+        }
+        let flags = Flags {
+            debug: false,
+            id: 1,
+            quiet: true,
+            verbose: Some(false),
+        };
+        let options = Options::new().bool_format(BoolFormat::Presence);
+        assert_eq!(
+            Form::to_string_with_options("f", &flags, true, &passthrough, &options)?,
+            "id=1&quiet"
+        );
+        let mut m = std::collections::BTreeMap::new();
+        m.insert("a", true);
+        m.insert("b", false);
+        m.insert("c", true);
+        assert_eq!(
+            Form::to_string_with_options("m", &m, true, &passthrough, &options)?,
+            "a&c"
+        );
+        assert!(matches!(
+            Form::to_string_with_options("f", &flags, false, &passthrough, &options),
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
+        Ok(())
     }
 }
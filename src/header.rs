@@ -0,0 +1,202 @@
+// Copyright 2022 Jonathan Giddy
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::Serialize;
+
+use crate::spec::extend_header_value;
+use crate::{Options, QuerylizerError};
+
+/// Serialize a `simple` value into a header value.
+///
+/// Header values are not percent-encoded. If the serialized value contains characters that are
+/// not allowed in a header (control characters such as CR and LF, or non-ASCII characters) then
+/// `QuerylizerError::InvalidHeaderValue` is returned.
+///
+/// # Example
+///
+/// ```
+/// use querylizer::to_header_value;
+/// let v = to_header_value(&["blue", "black"], false).unwrap();
+/// assert_eq!(v, "blue,black");
+/// ```
+pub fn to_header_value<T>(value: &T, explode: bool) -> Result<HeaderValue, QuerylizerError>
+where
+    T: ?Sized + Serialize,
+{
+    to_header_value_with_options(value, explode, &Options::default())
+}
+
+/// Serialize a `simple` value into a header value, using the provided options.
+///
+/// # Example
+///
+/// ```
+/// use querylizer::{to_header_value_with_options, BoolFormat, Options};
+/// let options = Options::new().bool_format(BoolFormat::OneZero);
+/// let v = to_header_value_with_options(&true, false, &options).unwrap();
+/// assert_eq!(v, "1");
+/// ```
+pub fn to_header_value_with_options<T>(
+    value: &T,
+    explode: bool,
+    options: &Options,
+) -> Result<HeaderValue, QuerylizerError>
+where
+    T: ?Sized + Serialize,
+{
+    let mut s = String::new();
+    extend_header_value(&mut s, value, explode, options)?;
+    HeaderValue::from_str(&s).map_err(|_| QuerylizerError::InvalidHeaderValue)
+}
+
+/// Serialize a `simple` value and insert it into a header map, replacing any existing values.
+///
+/// The name may be a `HeaderName` or a string. A name that is not a valid header name returns
+/// `QuerylizerError::InvalidHeaderValue`.
+///
+/// # Example
+///
+/// ```
+/// use http::HeaderMap;
+/// use querylizer::insert_header;
+/// #[derive(serde::Serialize)]
+/// struct Rgb {
+///     r: u8,
+///     g: u8,
+///     b: u8,
+/// }
+/// let mut headers = HeaderMap::new();
+/// insert_header(&mut headers, "x-color", &Rgb { r: 100, g: 200, b: 150 }, true).unwrap();
+/// assert_eq!(headers["x-color"], "r=100,g=200,b=150");
+/// ```
+pub fn insert_header<K, T>(
+    headers: &mut HeaderMap,
+    name: K,
+    value: &T,
+    explode: bool,
+) -> Result<(), QuerylizerError>
+where
+    K: TryInto<HeaderName>,
+    T: ?Sized + Serialize,
+{
+    insert_header_with_options(headers, name, value, explode, &Options::default())
+}
+
+/// Serialize a `simple` value and insert it into a header map, using the provided options.
+pub fn insert_header_with_options<K, T>(
+    headers: &mut HeaderMap,
+    name: K,
+    value: &T,
+    explode: bool,
+    options: &Options,
+) -> Result<(), QuerylizerError>
+where
+    K: TryInto<HeaderName>,
+    T: ?Sized + Serialize,
+{
+    let name = name
+        .try_into()
+        .map_err(|_| QuerylizerError::InvalidHeaderValue)?;
+    headers.insert(name, to_header_value_with_options(value, explode, options)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use http::HeaderMap;
+    use serde::Serialize;
+
+    use crate::{NullPolicy, Options, QuerylizerError};
+
+    use super::{insert_header, insert_header_with_options, to_header_value};
+
+    #[test]
+    fn test_str() -> Result<(), QuerylizerError> {
+        assert_eq!(to_header_value("a b/c%d", false)?, "a b/c%d");
+        assert_eq!(to_header_value(&12u32, false)?, "12");
+        Ok(())
+    }
+
+    #[test]
+    fn test_seq() -> Result<(), QuerylizerError> {
+        assert_eq!(to_header_value(&["blue", "black"], false)?, "blue,black");
+        assert_eq!(to_header_value(&["blue", "black"], true)?, "blue,black");
+        Ok(())
+    }
+
+    #[test]
+    fn test_struct() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Test {
+            #[serde(rename = "R")]
+            r: u32,
+            #[serde(rename = "G")]
+            g: u32,
+        }
+        let test = Test { r: 100, g: 200 };
+        assert_eq!(to_header_value(&test, false)?, "R,100,G,200");
+        assert_eq!(to_header_value(&test, true)?, "R=100,G=200");
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(
+            to_header_value("a\r\nb", false),
+            Err(QuerylizerError::InvalidHeaderValue)
+        );
+        assert_eq!(
+            to_header_value("a\0b", false),
+            Err(QuerylizerError::InvalidHeaderValue)
+        );
+        assert_eq!(
+            to_header_value("caf\u{e9}", false),
+            Err(QuerylizerError::InvalidHeaderValue)
+        );
+    }
+
+    #[test]
+    fn test_insert() -> Result<(), QuerylizerError> {
+        let mut headers = HeaderMap::new();
+        insert_header(&mut headers, "x-id", &5u32, false)?;
+        insert_header(&mut headers, "x-id", &6u32, false)?;
+        assert_eq!(headers.get_all("x-id").iter().count(), 1);
+        assert_eq!(headers["x-id"], "6");
+        assert_eq!(
+            insert_header(&mut headers, "x-bad", "a\nb", false),
+            Err(QuerylizerError::InvalidHeaderValue)
+        );
+        assert!(!headers.contains_key("x-bad"));
+        assert_eq!(
+            insert_header(&mut headers, "x bad", &5u32, false),
+            Err(QuerylizerError::InvalidHeaderValue)
+        );
+        insert_header(&mut headers, http::header::ACCEPT, "text/plain", false)?;
+        assert_eq!(headers["accept"], "text/plain");
+        Ok(())
+    }
+
+    #[test]
+    fn test_options() -> Result<(), QuerylizerError> {
+        let mut headers = HeaderMap::new();
+        let options = Options::new().null_parameters(NullPolicy::Error);
+        assert!(matches!(
+            insert_header_with_options(&mut headers, "x-id", &None::<u32>, false, &options),
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
+        assert!(!headers.contains_key("x-id"));
+        Ok(())
+    }
+}
@@ -0,0 +1,1136 @@
+// Copyright 2022 Jonathan Giddy
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde::{ser, Serialize, Serializer};
+
+use crate::builder::reserve_methods;
+use crate::bytes::encode_bytes;
+use crate::float::write_float;
+use crate::probe::{is_none, key_name};
+use crate::{Builder, Config, Encoder, Options, QuerylizerError, Simple, Sink};
+
+enum State {
+    // Top-level outside any container
+    Outer,
+    // Inside a container, but no elements yet
+    InnerFirst,
+    // Inside a container after first element
+    InnerNext,
+}
+
+/// Serialize a value into an OpenAPI `label` path parameter.
+pub struct Label<'s, F, W = String>
+where
+    F: Encoder,
+    W: Sink + ?Sized,
+{
+    output: &'s mut W,
+    explode: bool,
+    encoder: &'s F,
+    options: Options,
+    state: State,
+    // Index of the next item in a sequence
+    index: usize,
+    // Encoded key of the next map value
+    key: String,
+    // An element of the current container was left out
+    omitted: bool,
+}
+
+impl<'s, F> Label<'s, F>
+where
+    F: Encoder,
+{
+    /// Create a builder for a `label` serializer, with `explode` set to `false`.
+    pub fn builder(encoder: &'s F) -> Builder<'s, Self, F> {
+        Builder::new(encoder, false)
+    }
+
+    /// Serialize a `label` value into a new string to be used for web requests.
+    ///
+    /// The value is always prefixed with a `.`.
+    ///
+    /// If `explode` is `false` then items are comma separated (`.item1,item2`) and, for maps and
+    /// structs, keys and values are comma separated (`.key1,value1,key2,value2`).
+    ///
+    /// If `explode` is `true` then items are separated with `.` (`.item1.item2`) and, for maps
+    /// and structs, keys and values are separated with `=` (`.key1=value1.key2=value2`)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use querylizer::{encode_path, Label};
+    /// let s = Label::to_string(&["blue", "moon"], false, &encode_path).unwrap();
+    /// assert_eq!(s, ".blue,moon".to_owned());
+    /// ```
+    pub fn to_string<T>(value: &T, explode: bool, encoder: &F) -> Result<String, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        Self::to_string_with_options(value, explode, encoder, &Options::default())
+    }
+
+    /// Serialize a `label` value into a new string, using the provided options.
+    pub fn to_string_with_options<T>(
+        value: &T,
+        explode: bool,
+        encoder: &F,
+        options: &Options,
+    ) -> Result<String, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut output = String::new();
+        Self::extend_with_options(&mut output, value, explode, encoder, options)?;
+        Ok(output)
+    }
+}
+
+impl<'s, F, W> Label<'s, F, W>
+where
+    F: Encoder,
+    W: Sink + ?Sized,
+{
+    /// Append a `label` value onto an existing string or other [`Sink`] to be used for web
+    /// requests.
+    ///
+    /// The value is always prefixed with a `.`.
+    ///
+    /// If `explode` is `false` then items are comma separated (`.item1,item2`) and, for maps and
+    /// structs, keys and values are comma separated (`.key1,value1,key2,value2`).
+    ///
+    /// If `explode` is `true` then items are separated with `.` (`.item1.item2`) and, for maps
+    /// and structs, keys and values are separated with `=` (`.key1=value1.key2=value2`)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use querylizer::{encode_path, Label};
+    /// let mut s = "https://example.com/v1/report".to_owned();
+    /// Label::extend(&mut s, &["blue", "moon"], true, &encode_path).unwrap();
+    /// assert_eq!(s, "https://example.com/v1/report.blue.moon".to_owned());
+    /// ```
+    pub fn extend<T>(
+        output: &mut W,
+        value: &T,
+        explode: bool,
+        encoder: &F,
+    ) -> Result<(), QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        Self::extend_with_options(output, value, explode, encoder, &Options::default())
+    }
+
+    /// Append a `label` value onto an existing string, using the provided options.
+    pub fn extend_with_options<T>(
+        output: &mut W,
+        value: &T,
+        explode: bool,
+        encoder: &F,
+        options: &Options,
+    ) -> Result<(), QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut serializer = Label {
+            output,
+            explode,
+            encoder,
+            options: *options,
+            state: State::Outer,
+            index: 0,
+            key: String::new(),
+            omitted: false,
+        };
+        value
+            .serialize(&mut serializer)
+            .map_err(|e| e.in_parameter("label", ""))?;
+        Ok(())
+    }
+
+    // Finish an empty sequence, map or struct. An empty `label` value is a single `.`. A container
+    // with every element left out is left out too.
+    fn end_empty(&mut self, rejected: &'static str) -> Result<(), QuerylizerError> {
+        self.state = State::Outer;
+        if std::mem::take(&mut self.omitted) {
+            return Ok(());
+        }
+        if self.options.empty_containers.write_empty(rejected)? {
+            self.output.write_char('.')?;
+        }
+        Ok(())
+    }
+}
+
+impl<'s, F> Builder<'s, Label<'s, F>, F>
+where
+    F: Encoder,
+{
+    /// Set whether to explode sequences, maps and structs.
+    pub fn explode(mut self, explode: bool) -> Self {
+        self.config.explode = explode;
+        self
+    }
+}
+
+impl<'s, F> Config<'s, Label<'s, F>, F>
+where
+    F: Encoder,
+{
+    /// Serialize a `label` value into a new string.
+    pub fn to_string<T>(&self, value: &T) -> Result<String, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        Label::to_string_with_options(value, self.explode, self.encoder, &self.options)
+    }
+
+    /// Append a `label` value onto an existing string or other sink.
+    pub fn extend<W, T>(&self, output: &mut W, value: &T) -> Result<(), QuerylizerError>
+    where
+        W: Sink + ?Sized,
+        T: ?Sized + Serialize,
+    {
+        Label::extend_with_options(output, value, self.explode, self.encoder, &self.options)
+    }
+
+    reserve_methods!("a `label` value");
+}
+
+impl<'a, 's, F, W> Serializer for &'a mut Label<'s, F, W>
+where
+    F: Encoder,
+    W: Sink + ?Sized,
+{
+    type Ok = ();
+
+    // The error type when some error occurs during serialization.
+    type Error = QuerylizerError;
+
+    // Associated types for keeping track of additional state while serializing
+    // compound data structures like sequences and maps. In this case no
+    // additional state is required beyond what is already stored in the
+    // Serializer struct.
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        match self.options.bool_format.text(v) {
+            Some(s) => self.serialize_str(s),
+            None => Err(QuerylizerError::unsupported_value("presence-only bool")),
+        }
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i32(i32::from(v))
+    }
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i32(i32::from(v))
+    }
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        let mut buffer = itoa::Buffer::new();
+        self.serialize_str(buffer.format(v))
+    }
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        let mut buffer = itoa::Buffer::new();
+        self.serialize_str(buffer.format(v))
+    }
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        let mut buffer = itoa::Buffer::new();
+        self.serialize_str(buffer.format(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        self.serialize_u32(u32::from(v))
+    }
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        self.serialize_u32(u32::from(v))
+    }
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        let mut buffer = itoa::Buffer::new();
+        self.serialize_str(buffer.format(v))
+    }
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        let mut buffer = itoa::Buffer::new();
+        self.serialize_str(buffer.format(v))
+    }
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        let mut buffer = itoa::Buffer::new();
+        self.serialize_str(buffer.format(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        let options = self.options;
+        write_float(v, &options, |s| self.serialize_str(s))
+    }
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        let options = self.options;
+        write_float(v, &options, |s| self.serialize_str(s))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        let mut buf = [0u8; 4];
+        let s = v.encode_utf8(&mut buf);
+        self.serialize_str(s)?;
+        Ok(())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        if let State::Outer = self.state {
+            self.output.write_char('.')?;
+        }
+        self.encoder.encode(v, self.output)?;
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        if let Some(s) = encode_bytes(v, self.options.bytes_encoding) {
+            return self.serialize_str(&s);
+        }
+        use ser::SerializeSeq;
+        let mut seq_serializer = self.serialize_seq(Some(v.len()))?;
+        for byte in v {
+            seq_serializer.serialize_element(byte)?;
+        }
+        SerializeSeq::end(seq_serializer)?;
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        if let State::Outer = self.state {
+            if self.options.null_parameters.write_none()? {
+                self.serialize_str("")
+            } else {
+                Ok(())
+            }
+        } else {
+            Err(QuerylizerError::unsupported_nesting("none"))
+        }
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        if let State::Outer = self.state {
+            if self.options.null_parameters.write_none()? {
+                self.serialize_str("")
+            } else {
+                Ok(())
+            }
+        } else {
+            Err(QuerylizerError::unsupported_nesting("unit"))
+        }
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        Err(QuerylizerError::unsupported_value("unit struct"))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        match self.state {
+            State::Outer => {
+                self.state = State::InnerFirst;
+                Ok(self)
+            }
+            _ => Err(QuerylizerError::unsupported_nesting("seq")),
+        }
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        match self.state {
+            State::Outer => {
+                self.state = State::InnerFirst;
+                Ok(self)
+            }
+            _ => Err(QuerylizerError::unsupported_nesting("tuple")),
+        }
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        match self.state {
+            State::Outer => {
+                self.state = State::InnerFirst;
+                Ok(self)
+            }
+            _ => Err(QuerylizerError::unsupported_nesting("tuple struct")),
+        }
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        match self.state {
+            State::Outer => {
+                self.state = State::InnerFirst;
+                Ok(self)
+            }
+            _ => Err(QuerylizerError::unsupported_nesting("tuple variant")),
+        }
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        match self.state {
+            State::Outer => {
+                self.state = State::InnerFirst;
+                Ok(self)
+            }
+            _ => Err(QuerylizerError::unsupported_nesting("map")),
+        }
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        match self.state {
+            State::Outer => {
+                self.state = State::InnerFirst;
+                Ok(self)
+            }
+            _ => Err(QuerylizerError::unsupported_nesting("struct")),
+        }
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        match self.state {
+            State::Outer => {
+                self.state = State::InnerFirst;
+                Ok(self)
+            }
+            _ => Err(QuerylizerError::unsupported_nesting("struct variant")),
+        }
+    }
+}
+
+macro_rules! seq_serializer {
+    ($trait:ty, $serialize:ident) => {
+        impl<'a, 's, F, W> $trait for &'a mut Label<'s, F, W>
+        where
+            F: Encoder,
+            W: Sink + ?Sized,
+        {
+            type Ok = ();
+            type Error = QuerylizerError;
+
+            fn $serialize<T>(&mut self, value: &T) -> Result<(), Self::Error>
+            where
+                T: ?Sized + Serialize,
+            {
+                match self.state {
+                    State::Outer => unreachable!(),
+                    State::InnerFirst => {
+                        self.state = State::InnerNext;
+                        self.output.write_char('.')?;
+                    }
+                    State::InnerNext => {
+                        self.output
+                            .write_char(if self.explode { '.' } else { ',' })?;
+                    }
+                }
+                let index = self.index;
+                self.index += 1;
+                value.serialize(&mut **self).map_err(|e| e.at_index(index))
+            }
+
+            fn end(self) -> Result<(), Self::Error> {
+                match self.state {
+                    State::Outer => unreachable!(),
+                    State::InnerFirst => self.end_empty("empty sequence"),
+                    State::InnerNext => {
+                        self.state = State::Outer;
+                        self.index = 0;
+                        Ok(())
+                    }
+                }
+            }
+        }
+    };
+}
+
+seq_serializer!(ser::SerializeSeq, serialize_element);
+seq_serializer!(ser::SerializeTuple, serialize_element);
+seq_serializer!(ser::SerializeTupleStruct, serialize_field);
+seq_serializer!(ser::SerializeTupleVariant, serialize_field);
+
+impl<'a, 's, F, W> ser::SerializeMap for &'a mut Label<'s, F, W>
+where
+    F: Encoder,
+    W: Sink + ?Sized,
+{
+    type Ok = ();
+    type Error = QuerylizerError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        // Hold the key until the value shows whether the entry is left out
+        self.key.clear();
+        Simple::extend_with_options(&mut self.key, key, false, self.encoder, &self.options)
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let none = is_none(value);
+        if none && !self.options.null_fields.write_none()? {
+            self.omitted = true;
+            return Ok(());
+        }
+        match self.state {
+            State::Outer => unreachable!(),
+            State::InnerFirst => {
+                self.state = State::InnerNext;
+                self.output.write_char('.')?;
+            }
+            State::InnerNext => {
+                self.output
+                    .write_char(if self.explode { '.' } else { ',' })?;
+            }
+        }
+        self.output.write_str(&self.key)?;
+        self.output
+            .write_char(if self.explode { '=' } else { ',' })?;
+        if none {
+            "".serialize(&mut **self)
+        } else {
+            value.serialize(&mut **self)
+        }
+    }
+
+    fn serialize_entry<K, V>(&mut self, key: &K, value: &V) -> Result<(), Self::Error>
+    where
+        K: ?Sized + Serialize,
+        V: ?Sized + Serialize,
+    {
+        self.serialize_key(key)?;
+        self.serialize_value(value)
+            .map_err(|e| e.at_key(&key_name(key)))
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        match self.state {
+            State::Outer => unreachable!(),
+            State::InnerFirst => self.end_empty("empty map"),
+            State::InnerNext => {
+                self.state = State::Outer;
+                Ok(())
+            }
+        }
+    }
+}
+
+macro_rules! struct_serializer {
+    ($trait:ty) => {
+        impl<'a, 's, F, W> $trait for &'a mut Label<'s, F, W>
+        where
+            F: Encoder,
+            W: Sink + ?Sized,
+        {
+            type Ok = ();
+            type Error = QuerylizerError;
+
+            fn serialize_field<T>(
+                &mut self,
+                key: &'static str,
+                value: &T,
+            ) -> Result<(), Self::Error>
+            where
+                T: ?Sized + Serialize,
+            {
+                let none = is_none(value);
+                if none
+                    && !self
+                        .options
+                        .null_fields
+                        .write_none()
+                        .map_err(|e| e.at_key(key))?
+                {
+                    self.omitted = true;
+                    return Ok(());
+                }
+                match self.state {
+                    State::Outer => unreachable!(),
+                    State::InnerFirst => {
+                        self.state = State::InnerNext;
+                        self.output.write_char('.')?;
+                    }
+                    State::InnerNext => {
+                        self.output
+                            .write_char(if self.explode { '.' } else { ',' })?;
+                    }
+                }
+                key.serialize(&mut **self)?;
+                match self.state {
+                    State::Outer => unreachable!(),
+                    _ => {
+                        self.output
+                            .write_char(if self.explode { '=' } else { ',' })?;
+                    }
+                }
+                if none {
+                    "".serialize(&mut **self)
+                } else {
+                    value.serialize(&mut **self).map_err(|e| e.at_key(key))
+                }
+            }
+
+            fn end(self) -> Result<(), Self::Error> {
+                match self.state {
+                    State::Outer => unreachable!(),
+                    State::InnerFirst => self.end_empty("empty struct"),
+                    State::InnerNext => {
+                        self.state = State::Outer;
+                        Ok(())
+                    }
+                }
+            }
+        }
+    };
+}
+
+struct_serializer!(ser::SerializeStruct);
+struct_serializer!(ser::SerializeStructVariant);
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+
+    use crate::bytes::Bytes;
+    use crate::probe::Pairs;
+    use crate::{
+        passthrough, BytesEncoding, EmptyPolicy, NullPolicy, Options, QuerylizerError, Unsupported,
+    };
+
+    use super::Label;
+
+    #[test]
+    fn test_bool() -> Result<(), QuerylizerError> {
+        assert_eq!(Label::to_string(&true, false, &passthrough)?, ".true");
+        assert_eq!(Label::to_string(&false, false, &passthrough)?, ".false");
+        Ok(())
+    }
+
+    #[test]
+    fn test_i8() -> Result<(), QuerylizerError> {
+        assert_eq!(Label::to_string(&-1i8, false, &passthrough)?, ".-1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_i16() -> Result<(), QuerylizerError> {
+        assert_eq!(Label::to_string(&-1i16, false, &passthrough)?, ".-1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_i32() -> Result<(), QuerylizerError> {
+        assert_eq!(Label::to_string(&-1i32, false, &passthrough)?, ".-1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_i64() -> Result<(), QuerylizerError> {
+        assert_eq!(Label::to_string(&-1i64, false, &passthrough)?, ".-1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_i128() -> Result<(), QuerylizerError> {
+        assert_eq!(Label::to_string(&-1i128, false, &passthrough)?, ".-1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_u8() -> Result<(), QuerylizerError> {
+        assert_eq!(Label::to_string(&1u8, false, &passthrough)?, ".1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_u16() -> Result<(), QuerylizerError> {
+        assert_eq!(Label::to_string(&1u16, false, &passthrough)?, ".1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_u32() -> Result<(), QuerylizerError> {
+        assert_eq!(Label::to_string(&1u32, false, &passthrough)?, ".1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_u64() -> Result<(), QuerylizerError> {
+        assert_eq!(Label::to_string(&1u64, false, &passthrough)?, ".1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_u128() -> Result<(), QuerylizerError> {
+        assert_eq!(Label::to_string(&1u128, false, &passthrough)?, ".1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_f32() -> Result<(), QuerylizerError> {
+        assert_eq!(Label::to_string(&0.25f32, false, &passthrough)?, ".0.25");
+        Ok(())
+    }
+
+    #[test]
+    fn test_f64() -> Result<(), QuerylizerError> {
+        assert_eq!(Label::to_string(&0.25f64, false, &passthrough)?, ".0.25");
+        Ok(())
+    }
+
+    #[test]
+    fn test_char() -> Result<(), QuerylizerError> {
+        assert_eq!(Label::to_string(&'d', false, &passthrough)?, ".d");
+        Ok(())
+    }
+
+    #[test]
+    fn test_str() -> Result<(), QuerylizerError> {
+        assert_eq!(Label::to_string(&"blue", false, &passthrough)?, ".blue");
+        Ok(())
+    }
+
+    #[test]
+    fn test_bytes() -> Result<(), QuerylizerError> {
+        assert_eq!(
+            Label::to_string(b"blue", false, &passthrough)?,
+            ".98,108,117,101"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_bytes_encoding() -> Result<(), QuerylizerError> {
+        let options = Options::new().bytes_encoding(BytesEncoding::Hex);
+        assert_eq!(
+            Label::to_string_with_options(&Bytes(b"hi?"), false, &passthrough, &options)?,
+            ".68693f"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_none() -> Result<(), QuerylizerError> {
+        assert_eq!(
+            Label::to_string::<Option<u32>>(&None, false, &passthrough)?,
+            "."
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_none_parameter() -> Result<(), QuerylizerError> {
+        let options = Options::new().null_parameters(NullPolicy::Omit);
+        assert_eq!(
+            Label::to_string_with_options::<Option<u32>>(&None, false, &passthrough, &options)?,
+            ""
+        );
+        let options = Options::new().null_parameters(NullPolicy::Error);
+        assert_eq!(
+            Label::to_string_with_options::<Option<u32>>(&None, false, &passthrough, &options),
+            Err(QuerylizerError::UnsupportedValue(Unsupported {
+                style: "label",
+                path: "".to_owned(),
+                rejected: "none",
+            }))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_some() -> Result<(), QuerylizerError> {
+        assert_eq!(Label::to_string(&Some(1u32), false, &passthrough)?, ".1");
+        assert_eq!(
+            Label::to_string(&Some(vec![1u32, 2]), true, &passthrough)?,
+            ".1.2"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_option_nested() {
+        assert!(matches!(
+            Label::to_string(&vec![Some(1u32), None], false, &passthrough),
+            Err(QuerylizerError::UnsupportedNesting(_))
+        ));
+    }
+
+    #[test]
+    fn test_unit() -> Result<(), QuerylizerError> {
+        assert_eq!(Label::to_string(&(), false, &passthrough)?, ".");
+        Ok(())
+    }
+
+    #[test]
+    fn test_unit_struct() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct T {}
+        assert!(matches!(
+            Label::to_string(&T {}, false, &passthrough),
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_unit_variant() -> Result<(), QuerylizerError> {
+        #[derive(Serialize, PartialEq, Eq, PartialOrd, Ord)]
+        enum E {
+            A,
+            #[serde(rename = "b")]
+            B,
+        }
+        assert_eq!(Label::to_string(&E::A, false, &passthrough)?, ".A");
+        assert_eq!(Label::to_string(&E::B, false, &passthrough)?, ".b");
+        assert_eq!(Label::to_string(&[E::A, E::B], true, &passthrough)?, ".A.b");
+        let mut m = std::collections::BTreeMap::new();
+        m.insert(E::A, E::B);
+        assert_eq!(Label::to_string(&m, true, &passthrough)?, ".A=b");
+        Ok(())
+    }
+
+    #[test]
+    fn test_newtype_struct() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Metres(u32);
+        assert_eq!(Label::to_string(&Metres(5), false, &passthrough)?, ".5");
+        Ok(())
+    }
+
+    #[test]
+    fn test_newtype_variant() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        enum E {
+            A(u32),
+        }
+        assert_eq!(Label::to_string(&E::A(5), false, &passthrough)?, ".5");
+        Ok(())
+    }
+
+    #[test]
+    fn test_seq() -> Result<(), QuerylizerError> {
+        let v = vec!["blue", "black", "brown"];
+        assert_eq!(
+            Label::to_string(&v, false, &passthrough)?,
+            ".blue,black,brown"
+        );
+        assert_eq!(
+            Label::to_string(&v, true, &passthrough)?,
+            ".blue.black.brown"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_tuple() -> Result<(), QuerylizerError> {
+        let t = ("blue", "black", "brown");
+        assert_eq!(
+            Label::to_string(&t, false, &passthrough)?,
+            ".blue,black,brown"
+        );
+        assert_eq!(
+            Label::to_string(&t, true, &passthrough)?,
+            ".blue.black.brown"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_tuple_struct() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Triple(&'static str, &'static str, &'static str);
+        let v = Triple("blue", "black", "brown");
+        assert_eq!(
+            Label::to_string(&v, false, &passthrough)?,
+            ".blue,black,brown"
+        );
+        assert_eq!(
+            Label::to_string(&v, true, &passthrough)?,
+            ".blue.black.brown"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_tuple_variant() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        enum E {
+            A(u32, char),
+        }
+        assert_eq!(
+            Label::to_string(&E::A(5, 'f'), false, &passthrough)?,
+            ".5,f"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_map() -> Result<(), QuerylizerError> {
+        let mut m = std::collections::BTreeMap::new();
+        m.insert("R", 100);
+        m.insert("G", 200);
+        m.insert("B", 150);
+        assert_eq!(
+            Label::to_string(&m, false, &passthrough)?,
+            ".B,150,G,200,R,100"
+        );
+        assert_eq!(
+            Label::to_string(&m, true, &passthrough)?,
+            ".B=150.G=200.R=100"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_struct() {
+        #[derive(Serialize)]
+        struct Test {
+            #[serde(rename = "R")]
+            r: u32,
+            #[serde(rename = "G")]
+            g: u32,
+            #[serde(rename = "B")]
+            b: u32,
+        }
+
+        let test = Test {
+            r: 100,
+            g: 200,
+            b: 150,
+        };
+        assert_eq!(
+            Label::to_string(&test, false, &passthrough).unwrap(),
+            ".R,100,G,200,B,150"
+        );
+        assert_eq!(
+            Label::to_string(&test, true, &passthrough).unwrap(),
+            ".R=100.G=200.B=150"
+        );
+    }
+
+    #[test]
+    fn test_struct_variant() {
+        #[derive(Serialize)]
+        struct Test {
+            #[serde(rename = "R")]
+            r: u32,
+            #[serde(rename = "G")]
+            g: u32,
+            #[serde(rename = "B")]
+            b: u32,
+        }
+        #[derive(Serialize)]
+        enum E {
+            T(Test),
+        }
+
+        let test = E::T(Test {
+            r: 100,
+            g: 200,
+            b: 150,
+        });
+        assert_eq!(
+            Label::to_string(&test, false, &passthrough).unwrap(),
+            ".R,100,G,200,B,150"
+        );
+        assert_eq!(
+            Label::to_string(&test, true, &passthrough).unwrap(),
+            ".R=100.G=200.B=150"
+        );
+    }
+
+    #[test]
+    fn test_unsupported_nesting() {
+        #[derive(Serialize)]
+        struct Test {
+            #[serde(rename = "R")]
+            r: u32,
+            #[serde(rename = "G")]
+            g: u32,
+            #[serde(rename = "B")]
+            b: u32,
+        }
+
+        #[derive(Serialize)]
+        struct Outer {
+            t: Test,
+        }
+        let test = Outer {
+            t: Test {
+                r: 100,
+                g: 200,
+                b: 150,
+            },
+        };
+        assert!(matches!(
+            Label::to_string(&test, false, &passthrough),
+            Err(QuerylizerError::UnsupportedNesting(_))
+        ));
+    }
+
+    #[test]
+    fn test_none_fields() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Test {
+            a: Option<u32>,
+            b: (),
+            c: u32,
+        }
+        let test = Test {
+            a: None,
+            b: (),
+            c: 3,
+        };
+        assert_eq!(Label::to_string(&test, false, &passthrough)?, ".c,3");
+        assert_eq!(Label::to_string(&test, true, &passthrough)?, ".c=3");
+        let options = Options::new().null_fields(NullPolicy::Empty);
+        assert_eq!(
+            Label::to_string_with_options(&test, true, &passthrough, &options)?,
+            ".a=.b=.c=3"
+        );
+        let options = Options::new().null_fields(NullPolicy::Error);
+        assert_eq!(
+            Label::to_string_with_options(&test, true, &passthrough, &options),
+            Err(QuerylizerError::UnsupportedValue(Unsupported {
+                style: "label",
+                path: "a".to_owned(),
+                rejected: "none",
+            }))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_some_fields() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Test {
+            a: Option<u32>,
+            b: Option<u32>,
+            c: u32,
+        }
+        let test = Test {
+            a: None,
+            b: Some(2),
+            c: 3,
+        };
+        assert_eq!(Label::to_string(&test, false, &passthrough)?, ".b,2,c,3");
+        assert_eq!(Label::to_string(&test, true, &passthrough)?, ".b=2.c=3");
+        let pairs = Pairs(&[("a", Some(1)), ("b", None)]);
+        assert_eq!(Label::to_string(&pairs, true, &passthrough)?, ".a=1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_none_values() -> Result<(), QuerylizerError> {
+        let pairs = Pairs(&[("a", None::<u32>), ("b", None)]);
+        let options = Options::new().null_fields(NullPolicy::Empty);
+        assert_eq!(
+            Label::to_string_with_options(&pairs, false, &passthrough, &options)?,
+            ".a,,b,"
+        );
+        // A map with every entry left out is left out too, rather than being empty
+        assert_eq!(Label::to_string(&pairs, false, &passthrough)?, "");
+        let options = Options::new().empty_containers(EmptyPolicy::Empty);
+        assert_eq!(
+            Label::to_string_with_options(&pairs, false, &passthrough, &options)?,
+            ""
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_containers() -> Result<(), QuerylizerError> {
+        let m: std::collections::BTreeMap<&str, u32> = std::collections::BTreeMap::new();
+        let options = Options::new().empty_containers(EmptyPolicy::Empty);
+        assert_eq!(
+            Label::to_string_with_options(&m, true, &passthrough, &options)?,
+            "."
+        );
+        let options = Options::new().empty_containers(EmptyPolicy::Omit);
+        assert_eq!(
+            Label::to_string_with_options(&m, true, &passthrough, &options)?,
+            ""
+        );
+        assert!(matches!(
+            Label::to_string(&m, true, &passthrough),
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
+        Ok(())
+    }
+}
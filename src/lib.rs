@@ -17,23 +17,144 @@ use std::fmt::Display;
 use serde::ser;
 use thiserror::Error;
 
+pub use builder::{Builder, Config};
+pub use cookie::Cookie;
+pub use de::{
+    from_deep_form_str, from_deep_object_str, from_form_str, from_label_str, from_matrix_str,
+    from_pipe_delimited_str, from_simple_str, from_space_delimited_str,
+};
 pub use deep::DeepObject;
 pub use deepform::DeepForm;
+pub use delimited::{PipeDelimited, SpaceDelimited};
+pub use encoder::{Encoder, PercentEncoder};
 pub use form::Form;
+#[cfg(feature = "http")]
+pub use header::{
+    insert_header, insert_header_with_options, to_header_value, to_header_value_with_options,
+};
+pub use label::Label;
+pub use matrix::Matrix;
+pub use options::{
+    BoolFormat, BytesEncoding, DeepArrays, EmptyPolicy, FloatFormat, NullPolicy, Options,
+};
+pub use query::QueryWriter;
 pub use simple::Simple;
+#[cfg(feature = "bytes")]
+pub use sink::BufMutSink;
+pub use sink::{CountingSink, FmtSink, IoSink, Sink};
+pub use spec::{serialize, Location, ParameterSpec, Style};
+pub use url::UrlBuilder;
 
 #[derive(Error, PartialEq, Debug)]
 pub enum QuerylizerError {
     #[error("serialization error")]
     SerializationError(String),
-    #[error("nested containers not supported")]
-    UnsupportedNesting,
-    #[error("unsupported value")]
-    UnsupportedValue,
+    #[error("deserialization error")]
+    DeserializationError(String),
+    #[error("nested containers not supported: {0}")]
+    UnsupportedNesting(Unsupported),
+    #[error("unsupported value: {0}")]
+    UnsupportedValue(Unsupported),
+    #[error("non-finite float not supported: {0}")]
+    NonFiniteFloat(Unsupported),
+    #[error("invalid header value")]
+    InvalidHeaderValue,
+    #[error("{0} style is not supported for {1} parameters")]
+    UnsupportedStyle(Style, Location),
+    #[error("{0} parameters cannot be added to a URL")]
+    UnsupportedLocation(Location),
+    #[error("no placeholder named `{0}` in the URL template")]
+    UnknownPlaceholder(String),
+    #[error("placeholder `{0}` in the URL template has no value")]
+    MissingPlaceholder(String),
+    #[error("write error: {0}")]
+    WriteError(String),
     #[error("unknown error")]
     Unknown,
 }
 
+impl QuerylizerError {
+    pub(crate) fn unsupported_nesting(rejected: &'static str) -> Self {
+        QuerylizerError::UnsupportedNesting(Unsupported {
+            rejected,
+            ..Unsupported::default()
+        })
+    }
+
+    pub(crate) fn unsupported_value(rejected: &'static str) -> Self {
+        QuerylizerError::UnsupportedValue(Unsupported {
+            rejected,
+            ..Unsupported::default()
+        })
+    }
+
+    // Errors are located as they return through the enclosing containers, so the path is built
+    // from the innermost segment outwards.
+    fn prepend(mut self, segment: std::fmt::Arguments) -> Self {
+        if let QuerylizerError::UnsupportedNesting(ref mut unsupported)
+        | QuerylizerError::UnsupportedValue(ref mut unsupported)
+        | QuerylizerError::NonFiniteFloat(ref mut unsupported) = self
+        {
+            let path = &unsupported.path;
+            unsupported.path = if path.is_empty() || path.starts_with('[') {
+                format!("{}{}", segment, path)
+            } else {
+                format!("{}.{}", segment, path)
+            };
+        }
+        self
+    }
+
+    pub(crate) fn at_key(self, key: &str) -> Self {
+        self.prepend(format_args!("{}", key))
+    }
+
+    pub(crate) fn at_index(self, index: usize) -> Self {
+        self.prepend(format_args!("[{}]", index))
+    }
+
+    // Set the style and add the parameter name. The outermost serializer is called last, so it
+    // decides the style that is reported.
+    pub(crate) fn in_parameter(self, style: &'static str, name: &str) -> Self {
+        let mut error = if name.is_empty() {
+            self
+        } else {
+            self.at_key(name)
+        };
+        if let QuerylizerError::UnsupportedNesting(ref mut unsupported)
+        | QuerylizerError::UnsupportedValue(ref mut unsupported)
+        | QuerylizerError::NonFiniteFloat(ref mut unsupported) = error
+        {
+            unsupported.style = style;
+        }
+        error
+    }
+}
+
+/// Details of a value that could not be serialized.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Unsupported {
+    /// The parameter style in use, such as `form` or `deepObject`.
+    pub style: &'static str,
+    /// The location of the value, such as `filter.tags[2]`. An empty path is the whole value.
+    pub path: String,
+    /// The serde data type that was rejected, such as `seq` or `unit struct`.
+    pub rejected: &'static str,
+}
+
+impl Display for Unsupported {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.rejected)?;
+        if !self.path.is_empty() {
+            write!(f, " at `{}`", self.path)?;
+        }
+        if !self.style.is_empty() {
+            write!(f, " in {} style", self.style)?;
+        }
+        Ok(())
+    }
+}
+
 impl ser::Error for QuerylizerError {
     fn custom<T>(msg: T) -> Self
     where
@@ -43,6 +164,15 @@ impl ser::Error for QuerylizerError {
     }
 }
 
+impl serde::de::Error for QuerylizerError {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        QuerylizerError::DeserializationError(msg.to_string())
+    }
+}
+
 // See https://datatracker.ietf.org/doc/html/rfc3986#appendix-A
 
 const UNRESERVED: &percent_encoding::AsciiSet = &percent_encoding::NON_ALPHANUMERIC
@@ -95,6 +225,17 @@ const WWW_FORM_URL_ENCODED: &percent_encoding::AsciiSet = &percent_encoding::NON
     .remove(b'.')
     .remove(b'_');
 
+// https://datatracker.ietf.org/doc/html/rfc6265#section-4.1.1 with `%` added to allow decoding,
+// and `=` added since it ends a cookie name
+const COOKIE_OCTETS: &percent_encoding::AsciiSet = &percent_encoding::CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'%')
+    .add(b',')
+    .add(b';')
+    .add(b'=')
+    .add(b'\\');
+
 /// Encode a string to allow it to be added to a URL path.
 pub fn encode_path(s: &str) -> impl Iterator<Item = &str> {
     percent_encoding::utf8_percent_encode(s, PATH_SIMPLE)
@@ -164,6 +305,28 @@ pub fn encode_www_form_urlencoded(s: &str) -> impl Iterator<Item = &str> {
     percent_encoding::utf8_percent_encode(s, WWW_FORM_URL_ENCODED)
 }
 
+/// Encode a string to allow it to be added to a `Cookie` header.
+///
+/// Characters that are not RFC 6265 cookie-octets (controls, whitespace, `"`, `,`, `;`, `\` and
+/// non-ASCII characters) are percent-encoded, along with `%` itself and `=`, which would end a
+/// cookie name.
+///
+/// # Example
+///
+/// ```
+/// use querylizer::{encode_cookie, Cookie};
+/// #[derive(serde::Serialize)]
+/// struct V {
+///     a: &'static str,
+/// }
+/// let v = V { a: "a red;car" };
+/// let s = Cookie::to_string(&v, &encode_cookie).unwrap();
+/// assert_eq!(s, "a=a%20red%3Bcar");
+/// ```
+pub fn encode_cookie(s: &str) -> impl Iterator<Item = &str> {
+    percent_encoding::utf8_percent_encode(s, COOKIE_OCTETS)
+}
+
 /// An identity function that does not encode any characters.
 ///
 /// This can be passed to the `querylizer` serializers if no encoding should be done.
@@ -189,7 +352,24 @@ where
     }
 }
 
+mod builder;
+mod bytes;
+mod cookie;
+mod de;
 mod deep;
 mod deepform;
+mod delimited;
+mod encoder;
+mod float;
 mod form;
+#[cfg(feature = "http")]
+mod header;
+mod label;
+mod matrix;
+mod options;
+mod probe;
+mod query;
 mod simple;
+mod sink;
+mod spec;
+mod url;
@@ -0,0 +1,340 @@
+// Copyright 2022 Jonathan Giddy
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::QuerylizerError;
+
+/// How sequences inside a `deepObject` value are written.
+///
+/// OpenAPI does not define arrays inside `deepObject` parameters, and servers disagree on the
+/// encoding they accept.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeepArrays {
+    /// Repeat the key for each item (`name[key]=a&name[key]=b`).
+    RepeatedKey,
+    /// Add empty brackets to the key for each item (`name[key][]=a&name[key][]=b`), as used by
+    /// Rails and PHP.
+    EmptyBrackets,
+    /// Add the index of each item to the key (`name[key][0]=a&name[key][1]=b`), as used by `qs`.
+    /// This is the only convention that supports containers inside sequences.
+    Indexed,
+    /// Join items with commas (`name[key]=a,b`).
+    CommaJoined,
+}
+
+/// How `None` values are written.
+///
+/// Unit values, including `serde_json::Value::Null`, follow the same policy as `None`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NullPolicy {
+    /// Leave the value out, including its key and separators.
+    Omit,
+    /// Write the key with an empty value (`key=`).
+    Empty,
+    /// Return `QuerylizerError::UnsupportedValue`.
+    Error,
+}
+
+impl NullPolicy {
+    // Check the policy for a `None` value, returning whether to write an empty value.
+    pub(crate) fn write_none(self) -> Result<bool, QuerylizerError> {
+        match self {
+            NullPolicy::Omit => Ok(false),
+            NullPolicy::Empty => Ok(true),
+            NullPolicy::Error => Err(QuerylizerError::unsupported_value("none")),
+        }
+    }
+}
+
+/// How empty sequences, maps and structs are written.
+///
+/// The `Empty` policy follows the "empty" examples in the OpenAPI specification: nothing for
+/// `simple`, `.` for `label`, `;name` for `matrix`, and `name=` for the query styles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmptyPolicy {
+    /// Leave the value out, including its key and separators.
+    Omit,
+    /// Write the value as an empty value for the style.
+    Empty,
+    /// Return `QuerylizerError::UnsupportedValue`.
+    Error,
+}
+
+impl EmptyPolicy {
+    // Check the policy for an empty container, returning whether to write an empty value.
+    pub(crate) fn write_empty(self, rejected: &'static str) -> Result<bool, QuerylizerError> {
+        match self {
+            EmptyPolicy::Omit => Ok(false),
+            EmptyPolicy::Empty => Ok(true),
+            EmptyPolicy::Error => Err(QuerylizerError::unsupported_value(rejected)),
+        }
+    }
+}
+
+/// How byte arrays, such as `serde_bytes` fields, are written.
+///
+/// OpenAPI uses `format: byte` for base64 encoded strings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BytesEncoding {
+    /// Write each byte as a decimal number, like a sequence of `u8` (`1,2,255`).
+    Decimal,
+    /// Standard base64 with padding (RFC 4648 section 4).
+    Base64,
+    /// URL and filename safe base64 without padding (RFC 4648 section 5).
+    Base64Url,
+    /// Lowercase hexadecimal.
+    Hex,
+}
+
+/// How floating point numbers are written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FloatFormat {
+    /// The shortest text that reads back as the same value, using an exponent for very large or
+    /// small values (`1e21`).
+    Shortest,
+    /// A fixed number of digits after the decimal point (`1.50`).
+    Fixed(usize),
+    /// The shortest text that reads back as the same value, without an exponent
+    /// (`1000000000000000000000`).
+    Decimal,
+}
+
+/// How booleans are written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoolFormat {
+    /// `true` and `false`.
+    TrueFalse,
+    /// `1` and `0`.
+    OneZero,
+    /// `yes` and `no`.
+    YesNo,
+    /// Write a `true` parameter as its name with no value (`verbose`), and leave out a `false`
+    /// parameter. This is only supported for a boolean `form` or `matrix` parameter value, or a
+    /// field or map entry of an exploded `form` or `matrix` value, which is written as its key.
+    /// Cookies need a value, so it is not supported for cookie parameters.
+    Presence,
+}
+
+impl BoolFormat {
+    // The text for a boolean, or `None` for `BoolFormat::Presence`.
+    pub(crate) fn text(self, v: bool) -> Option<&'static str> {
+        match self {
+            BoolFormat::TrueFalse => Some(if v { "true" } else { "false" }),
+            BoolFormat::OneZero => Some(if v { "1" } else { "0" }),
+            BoolFormat::YesNo => Some(if v { "yes" } else { "no" }),
+            BoolFormat::Presence => None,
+        }
+    }
+}
+
+/// Options that adjust how values are serialized.
+///
+/// Options are set using chained methods, starting from the defaults returned by
+/// `Options::new()`.
+///
+/// # Example
+///
+/// ```
+/// use querylizer::{encode_query, DeepArrays, DeepObject, Options};
+/// #[derive(serde::Serialize)]
+/// struct Filter {
+///     tags: Vec<&'static str>,
+/// }
+/// let filter = Filter { tags: vec!["red", "blue"] };
+/// let options = Options::new().deep_arrays(DeepArrays::Indexed);
+/// let s = DeepObject::to_string_with_options("filter", &filter, &encode_query, &options).unwrap();
+/// assert_eq!(s, "filter[tags][0]=red&filter[tags][1]=blue");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Options {
+    pub(crate) deep_arrays: DeepArrays,
+    pub(crate) null_fields: NullPolicy,
+    pub(crate) null_parameters: NullPolicy,
+    pub(crate) empty_containers: EmptyPolicy,
+    pub(crate) bytes_encoding: BytesEncoding,
+    pub(crate) float_format: FloatFormat,
+    pub(crate) finite_floats: bool,
+    pub(crate) bool_format: BoolFormat,
+}
+
+impl Options {
+    /// Create the default options.
+    pub fn new() -> Self {
+        Options {
+            deep_arrays: DeepArrays::RepeatedKey,
+            null_fields: NullPolicy::Omit,
+            null_parameters: NullPolicy::Empty,
+            empty_containers: EmptyPolicy::Error,
+            bytes_encoding: BytesEncoding::Decimal,
+            float_format: FloatFormat::Shortest,
+            finite_floats: true,
+            bool_format: BoolFormat::TrueFalse,
+        }
+    }
+
+    /// Set how sequences inside a `deepObject` value are written.
+    ///
+    /// The default is [`DeepArrays::RepeatedKey`].
+    pub fn deep_arrays(mut self, deep_arrays: DeepArrays) -> Self {
+        self.deep_arrays = deep_arrays;
+        self
+    }
+
+    /// Set how `None` values in struct fields and map entries are written.
+    ///
+    /// The default is [`NullPolicy::Omit`]. A struct or map with every field left out is left out
+    /// too, rather than following the [`EmptyPolicy`] for an empty value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use querylizer::{encode_query, Form};
+    /// #[derive(serde::Serialize)]
+    /// struct Filter {
+    ///     color: Option<&'static str>,
+    /// }
+    /// let s = Form::to_string("f", &Filter { color: None }, true, &encode_query).unwrap();
+    /// assert_eq!(s, "");
+    /// ```
+    pub fn null_fields(mut self, null_fields: NullPolicy) -> Self {
+        self.null_fields = null_fields;
+        self
+    }
+
+    /// Set how a `None` parameter value is written.
+    ///
+    /// The default is [`NullPolicy::Empty`]. Use [`NullPolicy::Omit`] to leave out optional
+    /// parameters that have no value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use querylizer::{encode_query, Form, NullPolicy, Options};
+    /// let options = Options::new().null_parameters(NullPolicy::Omit);
+    /// let s = Form::to_string_with_options("limit", &None::<u32>, true, &encode_query, &options)
+    ///     .unwrap();
+    /// assert_eq!(s, "");
+    /// ```
+    pub fn null_parameters(mut self, null_parameters: NullPolicy) -> Self {
+        self.null_parameters = null_parameters;
+        self
+    }
+
+    /// Set how empty sequences, maps and structs are written.
+    ///
+    /// The default is [`EmptyPolicy::Error`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use querylizer::{encode_query, EmptyPolicy, Form, Options};
+    /// let tags: Vec<&str> = Vec::new();
+    /// let options = Options::new().empty_containers(EmptyPolicy::Empty);
+    /// let s = Form::to_string_with_options("tags", &tags, true, &encode_query, &options).unwrap();
+    /// assert_eq!(s, "tags=");
+    /// ```
+    pub fn empty_containers(mut self, empty_containers: EmptyPolicy) -> Self {
+        self.empty_containers = empty_containers;
+        self
+    }
+
+    /// Set how byte arrays are written.
+    ///
+    /// The default is [`BytesEncoding::Decimal`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use querylizer::{encode_query, BytesEncoding, Form, Options};
+    /// // Serialized as bytes, like a `serde_bytes` field
+    /// struct Data<'a>(&'a [u8]);
+    /// impl serde::Serialize for Data<'_> {
+    ///     fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    ///         serializer.serialize_bytes(self.0)
+    ///     }
+    /// }
+    /// #[derive(serde::Serialize)]
+    /// struct Upload<'a> {
+    ///     data: Data<'a>,
+    /// }
+    /// let options = Options::new().bytes_encoding(BytesEncoding::Base64);
+    /// let upload = Upload { data: Data(b"hi?") };
+    /// let s = Form::to_string_with_options("u", &upload, true, &encode_query, &options).unwrap();
+    /// assert_eq!(s, "data=aGk%2F");
+    /// ```
+    pub fn bytes_encoding(mut self, bytes_encoding: BytesEncoding) -> Self {
+        self.bytes_encoding = bytes_encoding;
+        self
+    }
+
+    /// Set how floating point numbers are written.
+    ///
+    /// The default is [`FloatFormat::Shortest`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use querylizer::{encode_query, FloatFormat, Form, Options};
+    /// let options = Options::new().float_format(FloatFormat::Decimal);
+    /// let s = Form::to_string_with_options("max", &1e21, true, &encode_query, &options).unwrap();
+    /// assert_eq!(s, "max=1000000000000000000000");
+    /// ```
+    pub fn float_format(mut self, float_format: FloatFormat) -> Self {
+        self.float_format = float_format;
+        self
+    }
+
+    /// Set whether NaN and infinite floats return `QuerylizerError::NonFiniteFloat`, instead of
+    /// being written as `NaN`, `inf` or `-inf`.
+    ///
+    /// The default is `true`, since OpenAPI numbers cannot be NaN or infinite.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use querylizer::{passthrough, Form, Options};
+    /// assert!(Form::to_string("v", &f64::NAN, true, &passthrough).is_err());
+    /// let options = Options::new().finite_floats(false);
+    /// let s = Form::to_string_with_options("v", &f64::NAN, true, &passthrough, &options).unwrap();
+    /// assert_eq!(s, "v=NaN");
+    /// ```
+    pub fn finite_floats(mut self, finite_floats: bool) -> Self {
+        self.finite_floats = finite_floats;
+        self
+    }
+
+    /// Set how booleans are written.
+    ///
+    /// The default is [`BoolFormat::TrueFalse`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use querylizer::{encode_query, BoolFormat, Form, Options};
+    /// let options = Options::new().bool_format(BoolFormat::Presence);
+    /// let s = Form::to_string_with_options("verbose", &true, true, &encode_query, &options).unwrap();
+    /// assert_eq!(s, "verbose");
+    /// let s = Form::to_string_with_options("verbose", &false, true, &encode_query, &options).unwrap();
+    /// assert_eq!(s, "");
+    /// ```
+    pub fn bool_format(mut self, bool_format: BoolFormat) -> Self {
+        self.bool_format = bool_format;
+        self
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options::new()
+    }
+}
@@ -0,0 +1,247 @@
+// Copyright 2022 Jonathan Giddy
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde::{ser, Serialize, Serializer};
+
+use crate::QuerylizerError;
+
+/// Check whether a value serializes as `None` or unit, without writing any output.
+///
+/// Containers are rejected as soon as they start, so this only visits the outermost layer of
+/// the value (and any newtype wrappers around it).
+pub(crate) fn is_none<T>(value: &T) -> bool
+where
+    T: ?Sized + Serialize,
+{
+    matches!(value.serialize(Probe), Ok(Kind::None))
+}
+
+/// Return the value of a `bool`, or `None` for any other value, without writing any output.
+pub(crate) fn as_bool<T>(value: &T) -> Option<bool>
+where
+    T: ?Sized + Serialize,
+{
+    match value.serialize(Probe) {
+        Ok(Kind::Bool(v)) => Some(v),
+        _ => None,
+    }
+}
+
+// The outermost layer of a value
+enum Kind {
+    None,
+    Bool(bool),
+    Other,
+}
+
+struct Probe;
+
+impl Serializer for Probe {
+    type Ok = Kind;
+    type Error = QuerylizerError;
+
+    type SerializeSeq = ser::Impossible<Kind, QuerylizerError>;
+    type SerializeTuple = ser::Impossible<Kind, QuerylizerError>;
+    type SerializeTupleStruct = ser::Impossible<Kind, QuerylizerError>;
+    type SerializeTupleVariant = ser::Impossible<Kind, QuerylizerError>;
+    type SerializeMap = ser::Impossible<Kind, QuerylizerError>;
+    type SerializeStruct = ser::Impossible<Kind, QuerylizerError>;
+    type SerializeStructVariant = ser::Impossible<Kind, QuerylizerError>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        Ok(Kind::Bool(v))
+    }
+    fn serialize_i8(self, _v: i8) -> Result<Self::Ok, Self::Error> {
+        Ok(Kind::Other)
+    }
+    fn serialize_i16(self, _v: i16) -> Result<Self::Ok, Self::Error> {
+        Ok(Kind::Other)
+    }
+    fn serialize_i32(self, _v: i32) -> Result<Self::Ok, Self::Error> {
+        Ok(Kind::Other)
+    }
+    fn serialize_i64(self, _v: i64) -> Result<Self::Ok, Self::Error> {
+        Ok(Kind::Other)
+    }
+    fn serialize_i128(self, _v: i128) -> Result<Self::Ok, Self::Error> {
+        Ok(Kind::Other)
+    }
+    fn serialize_u8(self, _v: u8) -> Result<Self::Ok, Self::Error> {
+        Ok(Kind::Other)
+    }
+    fn serialize_u16(self, _v: u16) -> Result<Self::Ok, Self::Error> {
+        Ok(Kind::Other)
+    }
+    fn serialize_u32(self, _v: u32) -> Result<Self::Ok, Self::Error> {
+        Ok(Kind::Other)
+    }
+    fn serialize_u64(self, _v: u64) -> Result<Self::Ok, Self::Error> {
+        Ok(Kind::Other)
+    }
+    fn serialize_u128(self, _v: u128) -> Result<Self::Ok, Self::Error> {
+        Ok(Kind::Other)
+    }
+    fn serialize_f32(self, _v: f32) -> Result<Self::Ok, Self::Error> {
+        Ok(Kind::Other)
+    }
+    fn serialize_f64(self, _v: f64) -> Result<Self::Ok, Self::Error> {
+        Ok(Kind::Other)
+    }
+    fn serialize_char(self, _v: char) -> Result<Self::Ok, Self::Error> {
+        Ok(Kind::Other)
+    }
+    fn serialize_str(self, _v: &str) -> Result<Self::Ok, Self::Error> {
+        Ok(Kind::Other)
+    }
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        Ok(Kind::Other)
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Kind::None)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        // `Some(())` is not `None`, but `Some(true)` is still a `bool`
+        match value.serialize(Probe) {
+            Ok(Kind::Bool(v)) => Ok(Kind::Bool(v)),
+            _ => Ok(Kind::Other),
+        }
+    }
+
+    // `serde_json::Value::Null` serializes as unit.
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Kind::None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        Ok(Kind::Other)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        Ok(Kind::Other)
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(Kind::Other)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Err(QuerylizerError::Unknown)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Err(QuerylizerError::Unknown)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Err(QuerylizerError::Unknown)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(QuerylizerError::Unknown)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Err(QuerylizerError::Unknown)
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Err(QuerylizerError::Unknown)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(QuerylizerError::Unknown)
+    }
+}
+
+/// Render a map key for the path of an error.
+pub(crate) fn key_name<T>(key: &T) -> String
+where
+    T: ?Sized + Serialize,
+{
+    crate::Simple::to_string(key, false, &crate::passthrough).unwrap_or_default()
+}
+
+// A map that calls `serialize_key` and `serialize_value` separately, instead of
+// `serialize_entry`.
+#[cfg(test)]
+pub(crate) struct Pairs<'a, V>(pub(crate) &'a [(&'a str, V)]);
+
+#[cfg(test)]
+impl<'a, V> Serialize for Pairs<'a, V>
+where
+    V: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        use ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (key, value) in self.0 {
+            map.serialize_key(key)?;
+            map.serialize_value(value)?;
+        }
+        map.end()
+    }
+}
@@ -0,0 +1,346 @@
+// Copyright 2022 Jonathan Giddy
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::form::Delimiter;
+use crate::{DeepForm, DeepObject, Encoder, Form, Options, ParameterSpec, QuerylizerError};
+
+/// Append a parameter, adding `separator` before it.
+///
+/// Nothing is added if the parameter serializes to nothing or fails. Returns whether the parameter
+/// added any output.
+pub(crate) fn append<W>(
+    output: &mut String,
+    separator: &str,
+    write: W,
+) -> Result<bool, QuerylizerError>
+where
+    W: FnOnce(&mut String) -> Result<(), QuerylizerError>,
+{
+    let start = output.len();
+    output.push_str(separator);
+    let mark = output.len();
+    match write(output) {
+        Ok(()) if output.len() > mark => Ok(true),
+        Ok(()) => {
+            output.truncate(start);
+            Ok(false)
+        }
+        Err(e) => {
+            output.truncate(start);
+            Err(e)
+        }
+    }
+}
+
+/// Write a query string (or form body) of several parameters, adding `&` between them.
+///
+/// # Example
+///
+/// ```
+/// use querylizer::{encode_query, QueryWriter};
+/// #[derive(serde::Serialize)]
+/// struct Filter {
+///     color: &'static str,
+/// }
+/// let mut query = QueryWriter::new(&encode_query);
+/// query
+///     .form("tag", &["red", "blue"], true)?
+///     .deep_object("filter", &Filter { color: "green" })?
+///     .pipe_delimited("sort", &["name", "date"])?;
+/// assert_eq!(
+///     query.into_string(),
+///     "tag=red&tag=blue&filter[color]=green&sort=name|date"
+/// );
+/// # Ok::<(), querylizer::QuerylizerError>(())
+/// ```
+pub struct QueryWriter<'e, F>
+where
+    F: Encoder,
+{
+    output: String,
+    encoder: &'e F,
+    options: Options,
+    // Written before the next parameter: `?`, `&` or nothing
+    separator: &'static str,
+}
+
+impl<'e, F> QueryWriter<'e, F>
+where
+    F: Encoder,
+{
+    /// Create a writer for a new query string.
+    pub fn new(encoder: &'e F) -> Self {
+        QueryWriter {
+            output: String::new(),
+            encoder,
+            options: Options::default(),
+            separator: "",
+        }
+    }
+
+    /// Create a writer that appends to an existing string.
+    ///
+    /// Like [`UrlBuilder::build`](crate::UrlBuilder::build), a `?` is added before the first
+    /// parameter if the string has no query, and a `&` is added if it has a query that does not
+    /// end with `&`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use querylizer::{encode_query, QueryWriter};
+    /// let mut query = QueryWriter::from_string("https://example.com/v1/?".to_owned(), &encode_query);
+    /// query.form("a", &1, true)?.form("b", &2, true)?;
+    /// assert_eq!(query.into_string(), "https://example.com/v1/?a=1&b=2");
+    /// # Ok::<(), querylizer::QuerylizerError>(())
+    /// ```
+    pub fn from_string(output: String, encoder: &'e F) -> Self {
+        let separator = match output.find('?') {
+            None => "?",
+            Some(i) if i + 1 < output.len() && !output.ends_with('&') => "&",
+            Some(_) => "",
+        };
+        QueryWriter {
+            output,
+            encoder,
+            options: Options::default(),
+            separator,
+        }
+    }
+
+    /// Set the options used for the following parameters.
+    pub fn options(&mut self, options: Options) -> &mut Self {
+        self.options = options;
+        self
+    }
+
+    fn append<W>(&mut self, write: W) -> Result<&mut Self, QuerylizerError>
+    where
+        W: FnOnce(&mut String, &F, &Options) -> Result<(), QuerylizerError>,
+    {
+        let encoder = self.encoder;
+        let options = self.options;
+        if append(&mut self.output, self.separator, |output| {
+            write(output, encoder, &options)
+        })? {
+            self.separator = "&";
+        }
+        Ok(self)
+    }
+
+    /// Add a `form` parameter.
+    pub fn form<T>(
+        &mut self,
+        name: &str,
+        value: &T,
+        explode: bool,
+    ) -> Result<&mut Self, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.append(|output, encoder, options| {
+            Form::extend_with_options(output, name, value, explode, encoder, options)
+        })
+    }
+
+    /// Add a `spaceDelimited` parameter.
+    pub fn space_delimited<T>(
+        &mut self,
+        name: &str,
+        value: &T,
+    ) -> Result<&mut Self, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.append(|output, encoder, options| {
+            Form::extend_delimited(output, name, value, encoder, Delimiter::Space, options)
+        })
+    }
+
+    /// Add a `pipeDelimited` parameter.
+    pub fn pipe_delimited<T>(&mut self, name: &str, value: &T) -> Result<&mut Self, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.append(|output, encoder, options| {
+            Form::extend_delimited(output, name, value, encoder, Delimiter::Pipe, options)
+        })
+    }
+
+    /// Add a `deepObject` parameter.
+    pub fn deep_object<T>(&mut self, name: &str, value: &T) -> Result<&mut Self, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.append(|output, encoder, options| {
+            DeepObject::extend_with_options(output, name, value, encoder, options)
+        })
+    }
+
+    /// Add the fields of a struct as `form` parameters, or as `deepObject` parameters for the
+    /// fields named in `deep`.
+    pub fn deep_form<T>(
+        &mut self,
+        value: &T,
+        deep: &HashSet<&str>,
+    ) -> Result<&mut Self, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.append(|output, encoder, options| {
+            DeepForm::extend_with_options(output, "", value, encoder, deep, options)
+        })
+    }
+
+    /// Add a query parameter using the style and encoding in the spec.
+    ///
+    /// The spec selects the encoding function, so the encoder of the writer is not used.
+    pub fn parameter<T>(
+        &mut self,
+        spec: &ParameterSpec,
+        value: &T,
+    ) -> Result<&mut Self, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.append(|output, _, options| spec.extend_with_options(output, value, options))
+    }
+
+    /// The query string written so far.
+    pub fn as_str(&self) -> &str {
+        &self.output
+    }
+
+    /// Return the query string.
+    pub fn into_string(self) -> String {
+        self.output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use serde::Serialize;
+
+    use crate::{
+        encode_query, encode_www_form_urlencoded, passthrough, Location, NullPolicy, Options,
+        ParameterSpec, QuerylizerError,
+    };
+
+    use super::QueryWriter;
+
+    #[test]
+    fn test_separators() -> Result<(), QuerylizerError> {
+        let mut query = QueryWriter::new(&passthrough);
+        assert_eq!(query.as_str(), "");
+        query.form("a", &1, true)?;
+        assert_eq!(query.as_str(), "a=1");
+        query.form("b", &[2, 3], false)?.form("c", &[4, 5], true)?;
+        assert_eq!(query.as_str(), "a=1&b=2,3&c=4&c=5");
+        Ok(())
+    }
+
+    #[test]
+    fn test_styles() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Rgb {
+            #[serde(rename = "R")]
+            r: u32,
+            #[serde(rename = "G")]
+            g: u32,
+        }
+        let mut query = QueryWriter::new(&encode_query);
+        query
+            .space_delimited("s", &["a", "b"])?
+            .pipe_delimited("p", &["a", "b"])?
+            .deep_object("d", &Rgb { r: 1, g: 2 })?
+            .parameter(&ParameterSpec::new("x", Location::Query), "a/b")?;
+        assert_eq!(query.into_string(), "s=a%20b&p=a|b&d[R]=1&d[G]=2&x=a%2Fb");
+        Ok(())
+    }
+
+    #[test]
+    fn test_deep_form() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Inner {
+            a: u32,
+        }
+        #[derive(Serialize)]
+        struct Body {
+            x: &'static str,
+            y: Inner,
+        }
+        let mut deep = HashSet::new();
+        deep.insert("y");
+        let mut query = QueryWriter::new(&encode_www_form_urlencoded);
+        query.form("first", "a b", true)?.deep_form(
+            &Body {
+                x: "c d",
+                y: Inner { a: 1 },
+            },
+            &deep,
+        )?;
+        assert_eq!(query.into_string(), "first=a%20b&x=c%20d&y[a]=1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_string() -> Result<(), QuerylizerError> {
+        let mut query = QueryWriter::from_string("/v1?".to_owned(), &passthrough);
+        query.form("a", &1, true)?;
+        assert_eq!(query.as_str(), "/v1?a=1");
+        let mut query = QueryWriter::from_string("/v1?a=1".to_owned(), &passthrough);
+        query.form("b", &2, true)?;
+        assert_eq!(query.as_str(), "/v1?a=1&b=2");
+        let mut query = QueryWriter::from_string("/v1?a=1&".to_owned(), &passthrough);
+        query.form("b", &2, true)?;
+        assert_eq!(query.as_str(), "/v1?a=1&b=2");
+        let mut query = QueryWriter::from_string("/v1".to_owned(), &passthrough);
+        query.form("a", &1, true)?.form("b", &2, true)?;
+        assert_eq!(query.as_str(), "/v1?a=1&b=2");
+        let mut query = QueryWriter::from_string("/v1".to_owned(), &passthrough);
+        query
+            .options(Options::new().null_parameters(NullPolicy::Omit))
+            .form("a", &None::<u32>, true)?;
+        assert_eq!(query.as_str(), "/v1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_omitted_parameter() -> Result<(), QuerylizerError> {
+        let mut query = QueryWriter::new(&passthrough);
+        query.options(Options::new().null_parameters(NullPolicy::Omit));
+        query
+            .form("a", &None::<u32>, true)?
+            .form("b", &1, true)?
+            .deep_object("c", &None::<u32>)?
+            .form("d", &2, true)?;
+        assert_eq!(query.as_str(), "b=1&d=2");
+        Ok(())
+    }
+
+    #[test]
+    fn test_error() -> Result<(), QuerylizerError> {
+        let mut query = QueryWriter::new(&passthrough);
+        query.form("a", &1, true)?;
+        assert!(query.form("b", &vec![vec![1]], true).is_err());
+        query.form("c", &3, true)?;
+        assert_eq!(query.as_str(), "a=1&c=3");
+        Ok(())
+    }
+}
@@ -14,7 +14,11 @@
 
 use serde::{ser, Serialize, Serializer};
 
-use crate::{EncodingFn, QuerylizerError};
+use crate::builder::reserve_methods;
+use crate::bytes::encode_bytes;
+use crate::float::write_float;
+use crate::probe::{is_none, key_name};
+use crate::{Builder, Config, Encoder, Options, QuerylizerError, Sink};
 
 enum State {
     // Top-level outside any container
@@ -26,20 +30,33 @@ enum State {
 }
 
 /// Serialize a value into an OpenAPI `simple` path parameter.
-pub struct Simple<'s, F>
+pub struct Simple<'s, F, W = String>
 where
-    F: for<'a> EncodingFn<'a>,
+    F: Encoder,
+    W: Sink + ?Sized,
 {
-    output: &'s mut String,
+    output: &'s mut W,
     explode: bool,
     encoder: &'s F,
+    options: Options,
     state: State,
+    // Index of the next item in a sequence
+    index: usize,
+    // Encoded key of the next map value
+    key: String,
+    // An element of the current container was left out
+    omitted: bool,
 }
 
 impl<'s, F> Simple<'s, F>
 where
-    F: for<'a> EncodingFn<'a>,
+    F: Encoder,
 {
+    /// Create a builder for a `simple` serializer, with `explode` set to `false`.
+    pub fn builder(encoder: &'s F) -> Builder<'s, Self, F> {
+        Builder::new(encoder, false)
+    }
+
     /// Serialize a `simple` value into a new string to be used for web requests.
     ///
     /// If `explode` is `false` then, for maps and structs, keys and values are comma separated
@@ -48,6 +65,8 @@ where
     /// If `explode` is `true` then, for maps and structs, keys and values are separated with `=`
     /// (`key1=value1,key2=value2`)
     ///
+    /// `Some(value)` is serialized as `value`, and `None` as an empty string.
+    ///
     /// # Example
     ///
     /// ```
@@ -56,21 +75,35 @@ where
     /// assert_eq!(s, "blue,moon".to_owned());
     /// ```
     pub fn to_string<T>(value: &T, explode: bool, encoder: &F) -> Result<String, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        Self::to_string_with_options(value, explode, encoder, &Options::default())
+    }
+
+    /// Serialize a `simple` value into a new string, using the provided options.
+    pub fn to_string_with_options<T>(
+        value: &T,
+        explode: bool,
+        encoder: &F,
+        options: &Options,
+    ) -> Result<String, QuerylizerError>
     where
         T: ?Sized + Serialize,
     {
         let mut output = String::new();
-        let mut serializer = Simple {
-            output: &mut output,
-            explode,
-            encoder,
-            state: State::Outer,
-        };
-        value.serialize(&mut serializer)?;
+        Self::extend_with_options(&mut output, value, explode, encoder, options)?;
         Ok(output)
     }
+}
 
-    /// Append a `simple` value onto an existing string to be used for web requests.
+impl<'s, F, W> Simple<'s, F, W>
+where
+    F: Encoder,
+    W: Sink + ?Sized,
+{
+    /// Append a `simple` value onto an existing string or other [`Sink`] to be used for web
+    /// requests.
     ///
     /// If `explode` is `false` then, for maps and structs, keys and values are comma separated
     /// (`key1,value1,key2,value2`).
@@ -87,11 +120,25 @@ where
     /// assert_eq!(s, "https://example.com/v1/blue,moon".to_owned());
     /// ```
     pub fn extend<T>(
-        output: &mut String,
+        output: &mut W,
         value: &T,
         explode: bool,
         encoder: &F,
     ) -> Result<(), QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        Self::extend_with_options(output, value, explode, encoder, &Options::default())
+    }
+
+    /// Append a `simple` value onto an existing string, using the provided options.
+    pub fn extend_with_options<T>(
+        output: &mut W,
+        value: &T,
+        explode: bool,
+        encoder: &F,
+        options: &Options,
+    ) -> Result<(), QuerylizerError>
     where
         T: ?Sized + Serialize,
     {
@@ -99,16 +146,69 @@ where
             output,
             explode,
             encoder,
+            options: *options,
             state: State::Outer,
+            index: 0,
+            key: String::new(),
+            omitted: false,
         };
-        value.serialize(&mut serializer)?;
+        value
+            .serialize(&mut serializer)
+            .map_err(|e| e.in_parameter("simple", ""))?;
         Ok(())
     }
+
+    // Finish an empty sequence, map or struct. An empty `simple` value is an empty string. A
+    // container with every element left out is left out too.
+    fn end_empty(&mut self, rejected: &'static str) -> Result<(), QuerylizerError> {
+        self.state = State::Outer;
+        if std::mem::take(&mut self.omitted) {
+            return Ok(());
+        }
+        self.options.empty_containers.write_empty(rejected)?;
+        Ok(())
+    }
+}
+
+impl<'s, F> Builder<'s, Simple<'s, F>, F>
+where
+    F: Encoder,
+{
+    /// Set whether to explode sequences, maps and structs.
+    pub fn explode(mut self, explode: bool) -> Self {
+        self.config.explode = explode;
+        self
+    }
+}
+
+impl<'s, F> Config<'s, Simple<'s, F>, F>
+where
+    F: Encoder,
+{
+    /// Serialize a `simple` value into a new string.
+    pub fn to_string<T>(&self, value: &T) -> Result<String, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        Simple::to_string_with_options(value, self.explode, self.encoder, &self.options)
+    }
+
+    /// Append a `simple` value onto an existing string or other sink.
+    pub fn extend<W, T>(&self, output: &mut W, value: &T) -> Result<(), QuerylizerError>
+    where
+        W: Sink + ?Sized,
+        T: ?Sized + Serialize,
+    {
+        Simple::extend_with_options(output, value, self.explode, self.encoder, &self.options)
+    }
+
+    reserve_methods!("a `simple` value");
 }
 
-impl<'a, 's, F> Serializer for &'a mut Simple<'s, F>
+impl<'a, 's, F, W> Serializer for &'a mut Simple<'s, F, W>
 where
-    F: for<'b> EncodingFn<'b>,
+    F: Encoder,
+    W: Sink + ?Sized,
 {
     type Ok = ();
 
@@ -128,7 +228,10 @@ where
     type SerializeStructVariant = Self;
 
     fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
-        self.serialize_str(if v { "true" } else { "false" })
+        match self.options.bool_format.text(v) {
+            Some(s) => self.serialize_str(s),
+            None => Err(QuerylizerError::unsupported_value("presence-only bool")),
+        }
     }
 
     fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
@@ -170,12 +273,12 @@ where
     }
 
     fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
-        let mut buffer = dtoa::Buffer::new();
-        self.serialize_str(buffer.format(v))
+        let options = self.options;
+        write_float(v, &options, |s| self.serialize_str(s))
     }
     fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
-        let mut buffer = dtoa::Buffer::new();
-        self.serialize_str(buffer.format(v))
+        let options = self.options;
+        write_float(v, &options, |s| self.serialize_str(s))
     }
 
     fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
@@ -186,11 +289,14 @@ where
     }
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
-        self.output.extend(self.encoder.call(v));
+        self.encoder.encode(v, self.output)?;
         Ok(())
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        if let Some(s) = encode_bytes(v, self.options.bytes_encoding) {
+            return self.serialize_str(&s);
+        }
         use ser::SerializeSeq;
         let mut seq_serializer = self.serialize_seq(Some(v.len()))?;
         for byte in v {
@@ -201,31 +307,47 @@ where
     }
 
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
-        Err(QuerylizerError::UnsupportedValue)
+        if let State::Outer = self.state {
+            if self.options.null_parameters.write_none()? {
+                self.serialize_str("")
+            } else {
+                Ok(())
+            }
+        } else {
+            Err(QuerylizerError::unsupported_nesting("none"))
+        }
     }
 
-    fn serialize_some<T>(self, _value: &T) -> Result<Self::Ok, Self::Error>
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
     where
         T: ?Sized + Serialize,
     {
-        Err(QuerylizerError::UnsupportedValue)
+        value.serialize(self)
     }
 
     fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
-        Err(QuerylizerError::UnsupportedValue)
+        if let State::Outer = self.state {
+            if self.options.null_parameters.write_none()? {
+                self.serialize_str("")
+            } else {
+                Ok(())
+            }
+        } else {
+            Err(QuerylizerError::unsupported_nesting("unit"))
+        }
     }
 
     fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
-        Err(QuerylizerError::UnsupportedValue)
+        Err(QuerylizerError::unsupported_value("unit struct"))
     }
 
     fn serialize_unit_variant(
         self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
-        Err(QuerylizerError::UnsupportedValue)
+        self.serialize_str(variant)
     }
 
     fn serialize_newtype_struct<T>(
@@ -258,7 +380,7 @@ where
                 self.state = State::InnerFirst;
                 Ok(self)
             }
-            _ => Err(QuerylizerError::UnsupportedNesting),
+            _ => Err(QuerylizerError::unsupported_nesting("seq")),
         }
     }
 
@@ -268,7 +390,7 @@ where
                 self.state = State::InnerFirst;
                 Ok(self)
             }
-            _ => Err(QuerylizerError::UnsupportedNesting),
+            _ => Err(QuerylizerError::unsupported_nesting("tuple")),
         }
     }
 
@@ -282,7 +404,7 @@ where
                 self.state = State::InnerFirst;
                 Ok(self)
             }
-            _ => Err(QuerylizerError::UnsupportedNesting),
+            _ => Err(QuerylizerError::unsupported_nesting("tuple struct")),
         }
     }
 
@@ -298,7 +420,7 @@ where
                 self.state = State::InnerFirst;
                 Ok(self)
             }
-            _ => Err(QuerylizerError::UnsupportedNesting),
+            _ => Err(QuerylizerError::unsupported_nesting("tuple variant")),
         }
     }
 
@@ -308,7 +430,7 @@ where
                 self.state = State::InnerFirst;
                 Ok(self)
             }
-            _ => Err(QuerylizerError::UnsupportedNesting),
+            _ => Err(QuerylizerError::unsupported_nesting("map")),
         }
     }
 
@@ -322,7 +444,7 @@ where
                 self.state = State::InnerFirst;
                 Ok(self)
             }
-            _ => Err(QuerylizerError::UnsupportedNesting),
+            _ => Err(QuerylizerError::unsupported_nesting("struct")),
         }
     }
 
@@ -338,16 +460,17 @@ where
                 self.state = State::InnerFirst;
                 Ok(self)
             }
-            _ => Err(QuerylizerError::UnsupportedNesting),
+            _ => Err(QuerylizerError::unsupported_nesting("struct variant")),
         }
     }
 }
 
 macro_rules! seq_serializer {
     ($trait:ty, $serialize:ident) => {
-        impl<'a, 's, F> $trait for &'a mut Simple<'s, F>
+        impl<'a, 's, F, W> $trait for &'a mut Simple<'s, F, W>
         where
-            F: for<'b> EncodingFn<'b>,
+            F: Encoder,
+            W: Sink + ?Sized,
         {
             type Ok = ();
             type Error = QuerylizerError;
@@ -360,18 +483,21 @@ macro_rules! seq_serializer {
                     State::Outer => unreachable!(),
                     State::InnerFirst => self.state = State::InnerNext,
                     State::InnerNext => {
-                        self.output.push(',');
+                        self.output.write_char(',')?;
                     }
                 }
-                value.serialize(&mut **self)
+                let index = self.index;
+                self.index += 1;
+                value.serialize(&mut **self).map_err(|e| e.at_index(index))
             }
 
             fn end(self) -> Result<(), Self::Error> {
                 match self.state {
                     State::Outer => unreachable!(),
-                    State::InnerFirst => Err(QuerylizerError::UnsupportedValue),
+                    State::InnerFirst => self.end_empty("empty sequence"),
                     State::InnerNext => {
                         self.state = State::Outer;
+                        self.index = 0;
                         Ok(())
                     }
                 }
@@ -385,9 +511,10 @@ seq_serializer!(ser::SerializeTuple, serialize_element);
 seq_serializer!(ser::SerializeTupleStruct, serialize_field);
 seq_serializer!(ser::SerializeTupleVariant, serialize_field);
 
-impl<'a, 's, F> ser::SerializeMap for &'a mut Simple<'s, F>
+impl<'a, 's, F, W> ser::SerializeMap for &'a mut Simple<'s, F, W>
 where
-    F: for<'b> EncodingFn<'b>,
+    F: Encoder,
+    W: Sink + ?Sized,
 {
     type Ok = ();
     type Error = QuerylizerError;
@@ -396,33 +523,51 @@ where
     where
         T: ?Sized + Serialize,
     {
-        match self.state {
-            State::Outer => unreachable!(),
-            State::InnerFirst => self.state = State::InnerNext,
-            State::InnerNext => {
-                self.output.push(',');
-            }
-        }
-        key.serialize(&mut **self)
+        // Hold the key until the value shows whether the entry is left out
+        self.key.clear();
+        Simple::extend_with_options(&mut self.key, key, false, self.encoder, &self.options)
     }
 
     fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
         T: ?Sized + Serialize,
     {
+        let none = is_none(value);
+        if none && !self.options.null_fields.write_none()? {
+            self.omitted = true;
+            return Ok(());
+        }
         match self.state {
             State::Outer => unreachable!(),
-            _ => {
-                self.output.push(if self.explode { '=' } else { ',' });
+            State::InnerFirst => self.state = State::InnerNext,
+            State::InnerNext => {
+                self.output.write_char(',')?;
             }
         }
-        value.serialize(&mut **self)
+        self.output.write_str(&self.key)?;
+        self.output
+            .write_char(if self.explode { '=' } else { ',' })?;
+        if none {
+            "".serialize(&mut **self)
+        } else {
+            value.serialize(&mut **self)
+        }
+    }
+
+    fn serialize_entry<K, V>(&mut self, key: &K, value: &V) -> Result<(), Self::Error>
+    where
+        K: ?Sized + Serialize,
+        V: ?Sized + Serialize,
+    {
+        self.serialize_key(key)?;
+        self.serialize_value(value)
+            .map_err(|e| e.at_key(&key_name(key)))
     }
 
     fn end(self) -> Result<(), Self::Error> {
         match self.state {
             State::Outer => unreachable!(),
-            State::InnerFirst => Err(QuerylizerError::UnsupportedValue),
+            State::InnerFirst => self.end_empty("empty map"),
             State::InnerNext => {
                 self.state = State::Outer;
                 Ok(())
@@ -433,9 +578,10 @@ where
 
 macro_rules! struct_serializer {
     ($trait:ty) => {
-        impl<'a, 's, F> $trait for &'a mut Simple<'s, F>
+        impl<'a, 's, F, W> $trait for &'a mut Simple<'s, F, W>
         where
-            F: for<'b> EncodingFn<'b>,
+            F: Encoder,
+            W: Sink + ?Sized,
         {
             type Ok = ();
             type Error = QuerylizerError;
@@ -448,27 +594,43 @@ macro_rules! struct_serializer {
             where
                 T: ?Sized + Serialize,
             {
+                let none = is_none(value);
+                if none
+                    && !self
+                        .options
+                        .null_fields
+                        .write_none()
+                        .map_err(|e| e.at_key(key))?
+                {
+                    self.omitted = true;
+                    return Ok(());
+                }
                 match self.state {
                     State::Outer => unreachable!(),
                     State::InnerFirst => self.state = State::InnerNext,
                     State::InnerNext => {
-                        self.output.push(',');
+                        self.output.write_char(',')?;
                     }
                 }
                 key.serialize(&mut **self)?;
                 match self.state {
                     State::Outer => unreachable!(),
                     _ => {
-                        self.output.push(if self.explode { '=' } else { ',' });
+                        self.output
+                            .write_char(if self.explode { '=' } else { ',' })?;
                     }
                 }
-                value.serialize(&mut **self)
+                if none {
+                    "".serialize(&mut **self)
+                } else {
+                    value.serialize(&mut **self).map_err(|e| e.at_key(key))
+                }
             }
 
             fn end(self) -> Result<(), Self::Error> {
                 match self.state {
                     State::Outer => unreachable!(),
-                    State::InnerFirst => Err(QuerylizerError::UnsupportedValue),
+                    State::InnerFirst => self.end_empty("empty struct"),
                     State::InnerNext => {
                         self.state = State::Outer;
                         Ok(())
@@ -486,7 +648,12 @@ struct_serializer!(ser::SerializeStructVariant);
 mod tests {
     use serde::Serialize;
 
-    use crate::{passthrough, QuerylizerError};
+    use crate::bytes::Bytes;
+    use crate::probe::Pairs;
+    use crate::{
+        encode_path, passthrough, BoolFormat, BytesEncoding, EmptyPolicy, FloatFormat, NullPolicy,
+        Options, QuerylizerError, Unsupported,
+    };
 
     use super::Simple;
 
@@ -590,54 +757,116 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_bytes_encoding() -> Result<(), QuerylizerError> {
+        let v = &Bytes(b"hi?");
+        let options = Options::new().bytes_encoding(BytesEncoding::Base64);
+        assert_eq!(
+            Simple::to_string_with_options(v, false, &encode_path, &options)?,
+            "aGk%2F"
+        );
+        assert_eq!(Simple::to_string(v, false, &passthrough)?, "104,105,63");
+        Ok(())
+    }
+
+    #[test]
+    fn test_float_format() -> Result<(), QuerylizerError> {
+        let options = Options::new().float_format(FloatFormat::Fixed(3));
+        assert_eq!(
+            Simple::to_string_with_options(&[1.0f32, 2.5], false, &passthrough, &options)?,
+            "1.000,2.500"
+        );
+        assert!(matches!(
+            Simple::to_string_with_options(&f64::NAN, false, &passthrough, &options),
+            Err(QuerylizerError::NonFiniteFloat(_))
+        ));
+        Ok(())
+    }
+
     #[test]
     fn test_none() -> Result<(), QuerylizerError> {
         assert_eq!(
-            Simple::to_string::<Option<u32>>(&None, false, &passthrough),
-            Err(QuerylizerError::UnsupportedValue)
+            Simple::to_string::<Option<u32>>(&None, false, &passthrough)?,
+            ""
         );
         Ok(())
     }
 
     #[test]
-    fn test_some() -> Result<(), QuerylizerError> {
+    fn test_none_parameter() -> Result<(), QuerylizerError> {
+        let options = Options::new().null_parameters(NullPolicy::Omit);
+        assert_eq!(
+            Simple::to_string_with_options::<Option<u32>>(&None, false, &passthrough, &options)?,
+            ""
+        );
+        let options = Options::new().null_parameters(NullPolicy::Error);
         assert_eq!(
-            Simple::to_string(&Some(1u32), false, &passthrough),
-            Err(QuerylizerError::UnsupportedValue)
+            Simple::to_string_with_options::<Option<u32>>(&None, false, &passthrough, &options),
+            Err(QuerylizerError::UnsupportedValue(Unsupported {
+                style: "simple",
+                path: "".to_owned(),
+                rejected: "none",
+            }))
         );
         Ok(())
     }
 
     #[test]
-    fn test_unit() -> Result<(), QuerylizerError> {
+    fn test_some() -> Result<(), QuerylizerError> {
+        assert_eq!(Simple::to_string(&Some(1u32), false, &passthrough)?, "1");
         assert_eq!(
-            Simple::to_string(&(), false, &passthrough),
-            Err(QuerylizerError::UnsupportedValue)
+            Simple::to_string(&Some("a b"), false, &crate::encode_path)?,
+            "a%20b"
+        );
+        assert_eq!(
+            Simple::to_string(&Some(vec![1u32, 2]), false, &passthrough)?,
+            "1,2"
         );
         Ok(())
     }
 
+    #[test]
+    fn test_option_nested() {
+        assert!(matches!(
+            Simple::to_string(&vec![Some(1u32), None], false, &passthrough),
+            Err(QuerylizerError::UnsupportedNesting(_))
+        ));
+    }
+
+    #[test]
+    fn test_unit() -> Result<(), QuerylizerError> {
+        assert_eq!(Simple::to_string(&(), false, &passthrough)?, "");
+        Ok(())
+    }
+
     #[test]
     fn test_unit_struct() -> Result<(), QuerylizerError> {
         #[derive(Serialize)]
         struct T {}
-        assert_eq!(
+        assert!(matches!(
             Simple::to_string(&T {}, false, &passthrough),
-            Err(QuerylizerError::UnsupportedValue)
-        );
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
         Ok(())
     }
 
     #[test]
     fn test_unit_variant() -> Result<(), QuerylizerError> {
-        #[derive(Serialize)]
+        #[derive(Serialize, PartialEq, Eq, PartialOrd, Ord)]
         enum E {
             A,
+            #[serde(rename = "b")]
+            B,
         }
+        assert_eq!(Simple::to_string(&E::A, false, &passthrough)?, "A");
+        assert_eq!(Simple::to_string(&E::B, false, &passthrough)?, "b");
         assert_eq!(
-            Simple::to_string(&E::A, false, &passthrough),
-            Err(QuerylizerError::UnsupportedValue)
+            Simple::to_string(&[E::A, E::B], false, &passthrough)?,
+            "A,b"
         );
+        let mut m = std::collections::BTreeMap::new();
+        m.insert(E::A, E::B);
+        assert_eq!(Simple::to_string(&m, true, &passthrough)?, "A=b");
         Ok(())
     }
 
@@ -814,9 +1043,166 @@ mod tests {
                 b: 150,
             },
         };
-        assert_eq!(
+        assert!(matches!(
             Simple::to_string(&test, false, &passthrough),
-            Err(QuerylizerError::UnsupportedNesting)
+            Err(QuerylizerError::UnsupportedNesting(_))
+        ));
+    }
+
+    #[test]
+    fn test_error_path() {
+        let mut m = std::collections::BTreeMap::new();
+        m.insert("R", vec![100]);
+        let error = Simple::to_string(&m, true, &passthrough).unwrap_err();
+        assert_eq!(
+            error,
+            QuerylizerError::UnsupportedNesting(Unsupported {
+                style: "simple",
+                path: "R".to_owned(),
+                rejected: "seq",
+            })
+        );
+        assert_eq!(
+            error.to_string(),
+            "nested containers not supported: seq at `R` in simple style"
         );
     }
+
+    #[test]
+    fn test_empty_containers() -> Result<(), QuerylizerError> {
+        let v: Vec<u32> = Vec::new();
+        let options = Options::new().empty_containers(EmptyPolicy::Empty);
+        assert_eq!(
+            Simple::to_string_with_options(&v, false, &passthrough, &options)?,
+            ""
+        );
+        let options = Options::new().empty_containers(EmptyPolicy::Omit);
+        assert_eq!(
+            Simple::to_string_with_options(&v, false, &passthrough, &options)?,
+            ""
+        );
+        assert_eq!(
+            Simple::to_string(&v, false, &passthrough),
+            Err(QuerylizerError::UnsupportedValue(Unsupported {
+                style: "simple",
+                path: "".to_owned(),
+                rejected: "empty sequence",
+            }))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_none_fields() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Test {
+            a: Option<u32>,
+            b: (),
+            c: u32,
+        }
+        let test = Test {
+            a: None,
+            b: (),
+            c: 3,
+        };
+        assert_eq!(Simple::to_string(&test, false, &passthrough)?, "c,3");
+        let options = Options::new().null_fields(NullPolicy::Empty);
+        assert_eq!(
+            Simple::to_string_with_options(&test, true, &passthrough, &options)?,
+            "a=,b=,c=3"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_some_fields() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Test {
+            a: Option<u32>,
+            b: Option<u32>,
+            c: u32,
+        }
+        let test = Test {
+            a: None,
+            b: Some(2),
+            c: 3,
+        };
+        assert_eq!(Simple::to_string(&test, false, &passthrough)?, "b,2,c,3");
+        assert_eq!(Simple::to_string(&test, true, &passthrough)?, "b=2,c=3");
+        let pairs = Pairs(&[("a", Some(1)), ("b", None)]);
+        assert_eq!(Simple::to_string(&pairs, true, &passthrough)?, "a=1");
+        assert_eq!(
+            Simple::to_string(&[Some(1), Some(2)], false, &passthrough)?,
+            "1,2"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_none_values() -> Result<(), QuerylizerError> {
+        let pairs = Pairs(&[("a", None::<u32>), ("b", None)]);
+        // A map with every entry left out is left out too, rather than being empty
+        assert_eq!(Simple::to_string(&pairs, false, &passthrough)?, "");
+        let options = Options::new().empty_containers(EmptyPolicy::Empty);
+        assert_eq!(
+            Simple::to_string_with_options(&pairs, false, &passthrough, &options)?,
+            ""
+        );
+        let options = Options::new().null_fields(NullPolicy::Empty);
+        assert_eq!(
+            Simple::to_string_with_options(&pairs, true, &passthrough, &options)?,
+            "a=,b="
+        );
+        let options = Options::new().null_fields(NullPolicy::Error);
+        assert!(matches!(
+            Simple::to_string_with_options(&pairs, false, &passthrough, &options),
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_value() -> Result<(), QuerylizerError> {
+        use serde_json::json;
+
+        let to_string = |value| Simple::to_string(&value, true, &passthrough);
+        assert_eq!(to_string(json!(null))?, "");
+        assert_eq!(to_string(json!(true))?, "true");
+        assert_eq!(to_string(json!(12))?, "12");
+        assert_eq!(to_string(json!(-12))?, "-12");
+        assert_eq!(to_string(json!(1.5))?, "1.5");
+        assert_eq!(to_string(json!("a b"))?, "a b");
+        assert_eq!(to_string(json!([1, "a", false]))?, "1,a,false");
+        assert_eq!(to_string(json!({"a": 1, "b": null, "c": "x"}))?, "a=1,c=x");
+        assert!(matches!(
+            to_string(json!([[1]])),
+            Err(QuerylizerError::UnsupportedNesting(_))
+        ));
+        let options = Options::new().null_fields(NullPolicy::Empty);
+        assert_eq!(
+            Simple::to_string_with_options(
+                &json!({"a": 1, "b": null}),
+                true,
+                &passthrough,
+                &options
+            )?,
+            "a=1,b="
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_bool_format() -> Result<(), QuerylizerError> {
+        let options = Options::new().bool_format(BoolFormat::YesNo);
+        assert_eq!(
+            Simple::to_string_with_options(&[true, false], false, &passthrough, &options)?,
+            "yes,no"
+        );
+        let options = Options::new().bool_format(BoolFormat::Presence);
+        assert!(matches!(
+            Simple::to_string_with_options(&true, false, &passthrough, &options),
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
+        Ok(())
+    }
 }
@@ -0,0 +1,337 @@
+// Copyright 2022 Jonathan Giddy
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::QuerylizerError;
+
+/// An output target for the serializers.
+///
+/// Output is only ever appended, so a sink can stream into a buffer or writer. If serialization
+/// fails, the sink may hold part of the value.
+///
+/// Sinks are provided for `String` and `Vec<u8>`, for any `std::fmt::Write` using [`FmtSink`],
+/// for any `std::io::Write` using [`IoSink`], and for `bytes::BufMut` with the `bytes` feature.
+/// [`CountingSink`] counts the output without storing it.
+///
+/// # Example
+///
+/// ```
+/// use querylizer::{encode_query, Form};
+/// let mut body = b"id=1&".to_vec();
+/// Form::extend(&mut body, "tag", &["a", "b"], true, &encode_query)?;
+/// assert_eq!(body, b"id=1&tag=a&tag=b");
+/// # Ok::<(), querylizer::QuerylizerError>(())
+/// ```
+pub trait Sink {
+    /// Append a string.
+    fn write_str(&mut self, s: &str) -> Result<(), QuerylizerError>;
+
+    /// Append a character.
+    fn write_char(&mut self, c: char) -> Result<(), QuerylizerError> {
+        self.write_str(c.encode_utf8(&mut [0; 4]))
+    }
+}
+
+impl Sink for String {
+    fn write_str(&mut self, s: &str) -> Result<(), QuerylizerError> {
+        self.push_str(s);
+        Ok(())
+    }
+
+    fn write_char(&mut self, c: char) -> Result<(), QuerylizerError> {
+        self.push(c);
+        Ok(())
+    }
+}
+
+impl Sink for Vec<u8> {
+    fn write_str(&mut self, s: &str) -> Result<(), QuerylizerError> {
+        self.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// A sink that counts the bytes written, without storing them.
+///
+/// Serializing into a `CountingSink` gives the exact length of the output, to reserve capacity
+/// before serializing the value again.
+///
+/// # Example
+///
+/// ```
+/// use querylizer::{encode_query, CountingSink, Form};
+/// let mut counter = CountingSink::new();
+/// Form::extend(&mut counter, "q", "a b", true, &encode_query)?;
+/// assert_eq!(counter.count(), "q=a%20b".len());
+/// # Ok::<(), querylizer::QuerylizerError>(())
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CountingSink {
+    count: usize,
+}
+
+impl CountingSink {
+    /// Create a sink with a count of zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of bytes written.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl Sink for CountingSink {
+    fn write_str(&mut self, s: &str) -> Result<(), QuerylizerError> {
+        self.count += s.len();
+        Ok(())
+    }
+
+    fn write_char(&mut self, c: char) -> Result<(), QuerylizerError> {
+        self.count += c.len_utf8();
+        Ok(())
+    }
+}
+
+/// A sink that writes to a `std::fmt::Write`, such as a `Formatter`.
+///
+/// # Example
+///
+/// ```
+/// use std::fmt;
+/// use querylizer::{passthrough, FmtSink, Simple};
+/// struct Ids(Vec<u32>);
+/// impl fmt::Display for Ids {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         Simple::extend(&mut FmtSink(f), &self.0, false, &passthrough).map_err(|_| fmt::Error)
+///     }
+/// }
+/// assert_eq!(Ids(vec![1, 2]).to_string(), "1,2");
+/// ```
+pub struct FmtSink<W>(pub W);
+
+impl<W> Sink for FmtSink<W>
+where
+    W: std::fmt::Write,
+{
+    fn write_str(&mut self, s: &str) -> Result<(), QuerylizerError> {
+        self.0
+            .write_str(s)
+            .map_err(|e| QuerylizerError::WriteError(e.to_string()))
+    }
+}
+
+/// A sink that writes to a `std::io::Write`, such as a socket or file.
+///
+/// Each string is written with `write_all`, so wrap unbuffered writers in a `BufWriter`.
+pub struct IoSink<W>(pub W);
+
+impl<W> Sink for IoSink<W>
+where
+    W: std::io::Write,
+{
+    fn write_str(&mut self, s: &str) -> Result<(), QuerylizerError> {
+        self.0
+            .write_all(s.as_bytes())
+            .map_err(|e| QuerylizerError::WriteError(e.to_string()))
+    }
+}
+
+/// A sink that writes to a `bytes::BufMut`, such as a `BytesMut`.
+///
+/// Writing more than the buffer has room for, such as past the end of a `&mut [u8]`, returns
+/// `QuerylizerError::WriteError` instead of panicking.
+#[cfg(feature = "bytes")]
+pub struct BufMutSink<B>(pub B);
+
+#[cfg(feature = "bytes")]
+impl<B> Sink for BufMutSink<B>
+where
+    B: bytes::BufMut,
+{
+    fn write_str(&mut self, s: &str) -> Result<(), QuerylizerError> {
+        if self.0.remaining_mut() < s.len() {
+            return Err(QuerylizerError::WriteError(
+                "not enough room in buffer".to_owned(),
+            ));
+        }
+        self.0.put_slice(s.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl Sink for bytes::BytesMut {
+    fn write_str(&mut self, s: &str) -> Result<(), QuerylizerError> {
+        self.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+}
+
+// A sink that writes a separator before the first output, so a value that writes nothing also
+// leaves out its separator.
+pub(crate) struct Separated<'a, S>
+where
+    S: Sink + ?Sized,
+{
+    inner: &'a mut S,
+    separator: &'static str,
+    written: bool,
+}
+
+impl<'a, S> Separated<'a, S>
+where
+    S: Sink + ?Sized,
+{
+    pub(crate) fn new(inner: &'a mut S, separator: &'static str) -> Self {
+        Separated {
+            inner,
+            separator,
+            written: false,
+        }
+    }
+
+    // Whether anything, including the separator, was written.
+    pub(crate) fn written(&self) -> bool {
+        self.written
+    }
+}
+
+impl<'a, S> Sink for Separated<'a, S>
+where
+    S: Sink + ?Sized,
+{
+    fn write_str(&mut self, s: &str) -> Result<(), QuerylizerError> {
+        if s.is_empty() {
+            return Ok(());
+        }
+        if !self.written {
+            self.written = true;
+            self.inner.write_str(self.separator)?;
+        }
+        self.inner.write_str(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+
+    use crate::{passthrough, Cookie, Form, QuerylizerError};
+
+    use super::{CountingSink, FmtSink, IoSink, Separated, Sink};
+
+    #[test]
+    fn test_vec() -> Result<(), QuerylizerError> {
+        let mut v = Vec::new();
+        v.write_str("a")?;
+        v.write_char('é')?;
+        assert_eq!(v, "aé".as_bytes());
+        Ok(())
+    }
+
+    #[test]
+    fn test_counting() -> Result<(), QuerylizerError> {
+        let mut counter = CountingSink::new();
+        counter.write_str("ab")?;
+        counter.write_char('é')?;
+        assert_eq!(counter.count(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fmt() -> Result<(), QuerylizerError> {
+        let mut s = String::new();
+        Form::extend(&mut FmtSink(&mut s), "a", &[1, 2], true, &passthrough)?;
+        assert_eq!(s, "a=1&a=2");
+        Ok(())
+    }
+
+    #[test]
+    fn test_io() -> Result<(), QuerylizerError> {
+        let mut v = Vec::new();
+        Form::extend(&mut IoSink(&mut v), "a", &[1, 2], false, &passthrough)?;
+        assert_eq!(v, b"a=1,2");
+        Ok(())
+    }
+
+    #[test]
+    fn test_io_error() {
+        let mut buffer = [0u8; 4];
+        let mut sink = IoSink(&mut buffer[..]);
+        assert!(matches!(
+            Form::extend(&mut sink, "a", &[1, 2], false, &passthrough),
+            Err(QuerylizerError::WriteError(_))
+        ));
+    }
+
+    #[test]
+    fn test_omitted_separator() -> Result<(), QuerylizerError> {
+        #[derive(Serialize)]
+        struct Cookies {
+            a: Option<u32>,
+            b: u32,
+            c: Option<u32>,
+        }
+        let value = Cookies {
+            a: None,
+            b: 2,
+            c: None,
+        };
+        let mut s = String::new();
+        Cookie::extend(&mut FmtSink(&mut s), &value, &passthrough)?;
+        assert_eq!(s, "b=2");
+        Ok(())
+    }
+
+    #[test]
+    fn test_separated() -> Result<(), QuerylizerError> {
+        let mut s = "a".to_owned();
+        let mut sink = Separated::new(&mut s, "&");
+        sink.write_str("")?;
+        assert!(!sink.written());
+        sink.write_str("b")?;
+        sink.write_str("c")?;
+        assert!(sink.written());
+        assert_eq!(s, "a&bc");
+        Ok(())
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn test_bytes() -> Result<(), QuerylizerError> {
+        use super::BufMutSink;
+
+        let mut b = bytes::BytesMut::new();
+        Form::extend(&mut b, "a", "x y", true, &crate::encode_query)?;
+        assert_eq!(&b[..], b"a=x%20y");
+        let mut v = Vec::new();
+        Form::extend(&mut BufMutSink(&mut v), "a", &1, true, &passthrough)?;
+        assert_eq!(v, b"a=1");
+        Ok(())
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn test_bytes_error() {
+        use super::BufMutSink;
+
+        let mut buffer = [0u8; 4];
+        let mut sink = BufMutSink(&mut buffer[..]);
+        assert!(matches!(
+            Form::extend(&mut sink, "a", &[1, 2], false, &passthrough),
+            Err(QuerylizerError::WriteError(_))
+        ));
+    }
+}
@@ -0,0 +1,463 @@
+// Copyright 2022 Jonathan Giddy
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use crate::form::Delimiter;
+use crate::{
+    passthrough, DeepObject, Form, Label, Matrix, Options, PercentEncoder, QuerylizerError, Simple,
+};
+
+/// The location of a parameter (OpenAPI `in`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Location {
+    Path,
+    Query,
+    Header,
+    Cookie,
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Location::Path => "path",
+            Location::Query => "query",
+            Location::Header => "header",
+            Location::Cookie => "cookie",
+        })
+    }
+}
+
+/// The serialization style of a parameter (OpenAPI `style`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Style {
+    Matrix,
+    Label,
+    Form,
+    Simple,
+    SpaceDelimited,
+    PipeDelimited,
+    DeepObject,
+}
+
+impl Display for Style {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Style::Matrix => "matrix",
+            Style::Label => "label",
+            Style::Form => "form",
+            Style::Simple => "simple",
+            Style::SpaceDelimited => "spaceDelimited",
+            Style::PipeDelimited => "pipeDelimited",
+            Style::DeepObject => "deepObject",
+        })
+    }
+}
+
+/// Description of an OpenAPI operation parameter.
+///
+/// Use `ParameterSpec::new` to get the OpenAPI defaults for a location: `form` style with
+/// `explode=true` for query and cookie parameters, and `simple` style with `explode=false` for path
+/// and header parameters.
+///
+/// # Example
+///
+/// ```
+/// use querylizer::{serialize, Location, ParameterSpec, Style};
+/// let spec = ParameterSpec::new("color", Location::Query);
+/// assert_eq!(serialize(&spec, &["blue", "black"]).unwrap(), "color=blue&color=black");
+///
+/// let spec = ParameterSpec::new("color", Location::Path).style(Style::Label);
+/// assert_eq!(serialize(&spec, &["blue", "black"]).unwrap(), ".blue,black");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParameterSpec<'a> {
+    pub name: &'a str,
+    pub location: Location,
+    pub style: Style,
+    pub explode: bool,
+    pub allow_reserved: bool,
+}
+
+impl<'a> ParameterSpec<'a> {
+    /// Create a parameter with the default style for its location.
+    pub fn new(name: &'a str, location: Location) -> Self {
+        let style = match location {
+            Location::Query | Location::Cookie => Style::Form,
+            Location::Path | Location::Header => Style::Simple,
+        };
+        ParameterSpec {
+            name,
+            location,
+            style,
+            explode: style == Style::Form,
+            allow_reserved: false,
+        }
+    }
+
+    /// Set the style, and reset `explode` to the default for that style.
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self.explode = style == Style::Form;
+        self
+    }
+
+    /// Set whether arrays and objects generate separate parameters.
+    pub fn explode(mut self, explode: bool) -> Self {
+        self.explode = explode;
+        self
+    }
+
+    /// Set whether reserved characters are allowed in query values without encoding.
+    pub fn allow_reserved(mut self, allow_reserved: bool) -> Self {
+        self.allow_reserved = allow_reserved;
+        self
+    }
+
+    /// Append a parameter value onto an existing string.
+    ///
+    /// The value is added without any separator, such as `&` for query parameters.
+    pub fn extend<T>(&self, output: &mut String, value: &T) -> Result<(), QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.extend_with_options(output, value, &Options::default())
+    }
+
+    /// Append a parameter value onto an existing string, using the provided options.
+    pub fn extend_with_options<T>(
+        &self,
+        output: &mut String,
+        value: &T,
+        options: &Options,
+    ) -> Result<(), QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        let name = self.name;
+        let explode = self.explode;
+        match (self.location, self.style) {
+            (Location::Path, Style::Simple) => {
+                Simple::extend_with_options(output, value, explode, &PercentEncoder::PATH, options)
+            }
+            (Location::Path, Style::Label) => {
+                Label::extend_with_options(output, value, explode, &PercentEncoder::PATH, options)
+            }
+            (Location::Path, Style::Matrix) => Matrix::extend_with_options(
+                output,
+                name,
+                value,
+                explode,
+                &PercentEncoder::PATH,
+                options,
+            ),
+            (Location::Query, style) if self.allow_reserved => extend_query(
+                output,
+                name,
+                value,
+                style,
+                explode,
+                &PercentEncoder::QUERY_ALLOW_RESERVED,
+                options,
+            ),
+            (Location::Query, style) => extend_query(
+                output,
+                name,
+                value,
+                style,
+                explode,
+                &PercentEncoder::QUERY,
+                options,
+            ),
+            (Location::Header, Style::Simple) => {
+                extend_header_value(output, value, explode, options)
+            }
+            (Location::Cookie, Style::Form) => Form::extend_cookie(
+                output,
+                name,
+                value,
+                explode,
+                &PercentEncoder::COOKIE,
+                options,
+            ),
+            (location, style) => Err(QuerylizerError::UnsupportedStyle(style, location)),
+        }
+    }
+}
+
+fn extend_query<T, F>(
+    output: &mut String,
+    name: &str,
+    value: &T,
+    style: Style,
+    explode: bool,
+    encoder: &F,
+    options: &Options,
+) -> Result<(), QuerylizerError>
+where
+    T: ?Sized + Serialize,
+    F: crate::Encoder,
+{
+    match style {
+        Style::Form => Form::extend_with_options(output, name, value, explode, encoder, options),
+        // An exploded delimited parameter is the same as an exploded form parameter.
+        Style::SpaceDelimited | Style::PipeDelimited if explode => {
+            Form::extend_with_options(output, name, value, true, encoder, options)
+        }
+        Style::SpaceDelimited => {
+            Form::extend_delimited(output, name, value, encoder, Delimiter::Space, options)
+        }
+        Style::PipeDelimited => {
+            Form::extend_delimited(output, name, value, encoder, Delimiter::Pipe, options)
+        }
+        Style::DeepObject => DeepObject::extend_with_options(output, name, value, encoder, options),
+        style => Err(QuerylizerError::UnsupportedStyle(style, Location::Query)),
+    }
+}
+
+// Allow visible ASCII, space and horizontal tab (RFC 7230 section 3.2).
+fn is_header_value(s: &str) -> bool {
+    s.bytes().all(|b| b == b'\t' || (b' '..=b'~').contains(&b))
+}
+
+// Append an unencoded `simple` header value, leaving the output unchanged if it contains
+// characters not allowed in a header.
+pub(crate) fn extend_header_value<T>(
+    output: &mut String,
+    value: &T,
+    explode: bool,
+    options: &Options,
+) -> Result<(), QuerylizerError>
+where
+    T: ?Sized + Serialize,
+{
+    let start = output.len();
+    Simple::extend_with_options(output, value, explode, &passthrough, options)?;
+    if is_header_value(&output[start..]) {
+        Ok(())
+    } else {
+        output.truncate(start);
+        Err(QuerylizerError::InvalidHeaderValue)
+    }
+}
+
+/// Serialize a parameter value into a new string, using the style and encoding in the spec.
+///
+/// Path parameters use `encode_path`, query parameters use `encode_query` (or
+/// `encode_query_allow_reserved` if `allow_reserved` is set), and cookie parameters use
+/// `encode_cookie`. Exploded cookie arrays and objects are written as separate cookies, separated
+/// with `; `. Header parameters are not encoded, but return
+/// `QuerylizerError::InvalidHeaderValue` if they contain characters not allowed in a header.
+///
+/// Combinations of style and location that OpenAPI does not define, such as `deepObject` in a
+/// path, return `QuerylizerError::UnsupportedStyle`.
+pub fn serialize<T>(spec: &ParameterSpec, value: &T) -> Result<String, QuerylizerError>
+where
+    T: ?Sized + Serialize,
+{
+    let mut output = String::new();
+    spec.extend(&mut output, value)?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+
+    use crate::{BoolFormat, Options, QuerylizerError};
+
+    use super::{serialize, Location, ParameterSpec, Style};
+
+    #[derive(Serialize)]
+    struct Rgb {
+        #[serde(rename = "R")]
+        r: u32,
+        #[serde(rename = "G")]
+        g: u32,
+    }
+
+    #[test]
+    fn test_defaults() {
+        let spec = ParameterSpec::new("id", Location::Query);
+        assert_eq!(spec.style, Style::Form);
+        assert!(spec.explode);
+        assert!(!spec.allow_reserved);
+        let spec = ParameterSpec::new("id", Location::Cookie);
+        assert_eq!(spec.style, Style::Form);
+        assert!(spec.explode);
+        let spec = ParameterSpec::new("id", Location::Path);
+        assert_eq!(spec.style, Style::Simple);
+        assert!(!spec.explode);
+        let spec = ParameterSpec::new("id", Location::Header);
+        assert_eq!(spec.style, Style::Simple);
+        assert!(!spec.explode);
+        let spec = ParameterSpec::new("id", Location::Query).style(Style::PipeDelimited);
+        assert!(!spec.explode);
+    }
+
+    #[test]
+    fn test_path() -> Result<(), QuerylizerError> {
+        let rgb = Rgb { r: 100, g: 200 };
+        let spec = ParameterSpec::new("color", Location::Path);
+        assert_eq!(serialize(&spec, "a b/c")?, "a%20b%2Fc");
+        assert_eq!(serialize(&spec.explode(true), &rgb)?, "R=100,G=200");
+        let spec = spec.style(Style::Label);
+        assert_eq!(serialize(&spec, &["a", "b"])?, ".a,b");
+        let spec = spec.style(Style::Matrix).explode(true);
+        assert_eq!(serialize(&spec, &rgb)?, ";R=100;G=200");
+        Ok(())
+    }
+
+    #[test]
+    fn test_query() -> Result<(), QuerylizerError> {
+        let spec = ParameterSpec::new("color", Location::Query);
+        assert_eq!(serialize(&spec, "a/b")?, "color=a%2Fb");
+        assert_eq!(serialize(&spec.allow_reserved(true), "a/b")?, "color=a/b");
+        assert_eq!(serialize(&spec.explode(false), &["a", "b"])?, "color=a,b");
+        let spec = spec.style(Style::SpaceDelimited);
+        assert_eq!(serialize(&spec, &["a", "b"])?, "color=a%20b");
+        assert_eq!(
+            serialize(&spec.explode(true), &["a", "b"])?,
+            "color=a&color=b"
+        );
+        let spec = spec.style(Style::PipeDelimited);
+        assert_eq!(serialize(&spec, &["a", "b"])?, "color=a|b");
+        let spec = spec.style(Style::DeepObject);
+        assert_eq!(
+            serialize(&spec, &Rgb { r: 100, g: 200 })?,
+            "color[R]=100&color[G]=200"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_header() -> Result<(), QuerylizerError> {
+        let spec = ParameterSpec::new("x-color", Location::Header);
+        assert_eq!(serialize(&spec, "a b/c")?, "a b/c");
+        assert_eq!(
+            serialize(&spec, "a\r\nb"),
+            Err(QuerylizerError::InvalidHeaderValue)
+        );
+        let mut s = "abc".to_owned();
+        assert_eq!(
+            spec.extend(&mut s, "a\r\nb"),
+            Err(QuerylizerError::InvalidHeaderValue)
+        );
+        assert_eq!(s, "abc");
+        Ok(())
+    }
+
+    #[test]
+    fn test_cookie() -> Result<(), QuerylizerError> {
+        let spec = ParameterSpec::new("id", Location::Cookie);
+        assert_eq!(serialize(&spec, "a;b")?, "id=a%3Bb");
+        assert_eq!(serialize(&spec.explode(false), &[1, 2])?, "id=1,2");
+        Ok(())
+    }
+
+    #[test]
+    fn test_cookie_explode() -> Result<(), QuerylizerError> {
+        let spec = ParameterSpec::new("id", Location::Cookie);
+        assert_eq!(serialize(&spec, &[1, 2])?, "id=1; id=2");
+        let spec = ParameterSpec::new("color", Location::Cookie);
+        assert_eq!(serialize(&spec, &Rgb { r: 100, g: 200 })?, "R=100; G=200");
+        Ok(())
+    }
+
+    #[test]
+    fn test_cookie_invalid_names() -> Result<(), QuerylizerError> {
+        let spec = ParameterSpec::new("a/b", Location::Cookie);
+        assert!(matches!(
+            serialize(&spec, &1),
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
+        // Exploded object keys are cookie names too
+        let mut colors = std::collections::BTreeMap::new();
+        colors.insert("dark=red", 1);
+        let spec = ParameterSpec::new("color", Location::Cookie);
+        assert_eq!(serialize(&spec, &colors)?, "dark%3Dred=1");
+        colors.insert("dark/red", 2);
+        assert!(matches!(
+            serialize(&spec, &colors),
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
+        #[derive(Serialize)]
+        struct Rgb {
+            #[serde(rename = "r(ed)")]
+            r: u32,
+        }
+        assert!(matches!(
+            serialize(&spec, &Rgb { r: 1 }),
+            Err(QuerylizerError::UnsupportedValue(_))
+        ));
+        // Keys inside an unexploded value are not names
+        assert_eq!(
+            serialize(&spec.explode(false), &Rgb { r: 1 })?,
+            "color=r(ed),1"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cookie_bool_presence() {
+        let options = Options::new().bool_format(BoolFormat::Presence);
+        let mut flags = std::collections::BTreeMap::new();
+        flags.insert("verbose", true);
+        for explode in [false, true] {
+            let spec = ParameterSpec::new("flags", Location::Cookie).explode(explode);
+            let mut s = String::new();
+            assert!(matches!(
+                spec.extend_with_options(&mut s, &true, &options),
+                Err(QuerylizerError::UnsupportedValue(_))
+            ));
+            let mut s = String::new();
+            assert!(matches!(
+                spec.extend_with_options(&mut s, &flags, &options),
+                Err(QuerylizerError::UnsupportedValue(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_unsupported_style() {
+        let spec = ParameterSpec::new("color", Location::Path).style(Style::DeepObject);
+        let error = serialize(&spec, &Rgb { r: 100, g: 200 }).unwrap_err();
+        assert_eq!(
+            error,
+            QuerylizerError::UnsupportedStyle(Style::DeepObject, Location::Path)
+        );
+        assert_eq!(
+            error.to_string(),
+            "deepObject style is not supported for path parameters"
+        );
+        let spec = ParameterSpec::new("color", Location::Query).style(Style::Simple);
+        assert_eq!(
+            serialize(&spec, "a"),
+            Err(QuerylizerError::UnsupportedStyle(
+                Style::Simple,
+                Location::Query
+            ))
+        );
+        let spec = ParameterSpec::new("color", Location::Header).style(Style::Form);
+        assert_eq!(
+            serialize(&spec, "a"),
+            Err(QuerylizerError::UnsupportedStyle(
+                Style::Form,
+                Location::Header
+            ))
+        );
+    }
+}
@@ -0,0 +1,268 @@
+// Copyright 2022 Jonathan Giddy
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde::Serialize;
+
+use crate::{Location, ParameterSpec, QuerylizerError};
+
+enum Segment<'t> {
+    // Text copied from the template
+    Literal(&'t str),
+    // A `{name}` placeholder and its serialized value
+    Placeholder(&'t str, Option<String>),
+}
+
+/// Build a request URL from a path template and parameters.
+///
+/// # Example
+///
+/// ```
+/// use querylizer::UrlBuilder;
+/// let mut url = UrlBuilder::new("https://example.com/v1/users/{id}/posts");
+/// url.path("id", "a/b")?;
+/// url.query("tag", &["red", "blue"])?;
+/// url.query("limit", &10)?;
+/// assert_eq!(
+///     url.build()?,
+///     "https://example.com/v1/users/a%2Fb/posts?tag=red&tag=blue&limit=10"
+/// );
+/// # Ok::<(), querylizer::QuerylizerError>(())
+/// ```
+pub struct UrlBuilder<'t> {
+    segments: Vec<Segment<'t>>,
+    // Query parameters, without a leading separator
+    query: String,
+}
+
+impl<'t> UrlBuilder<'t> {
+    /// Create a builder for a path template containing `{name}` placeholders.
+    ///
+    /// The template may include a scheme and host, and a query string to which parameters are
+    /// added.
+    pub fn new(template: &'t str) -> Self {
+        let mut segments = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            match rest[start..].find('}') {
+                None => break,
+                Some(len) => {
+                    if start > 0 {
+                        segments.push(Segment::Literal(&rest[..start]));
+                    }
+                    segments.push(Segment::Placeholder(&rest[start + 1..start + len], None));
+                    rest = &rest[start + len + 1..];
+                }
+            }
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Literal(rest));
+        }
+        UrlBuilder {
+            segments,
+            query: String::new(),
+        }
+    }
+
+    /// Fill a placeholder using the `simple` style, as for a path parameter with default style.
+    pub fn path<T>(&mut self, name: &str, value: &T) -> Result<&mut Self, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.parameter(&ParameterSpec::new(name, Location::Path), value)
+    }
+
+    /// Add a query parameter using the `form` style, as for a query parameter with default style.
+    pub fn query<T>(&mut self, name: &str, value: &T) -> Result<&mut Self, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.parameter(&ParameterSpec::new(name, Location::Query), value)
+    }
+
+    /// Fill a placeholder or add a query parameter, using the style and encoding in the spec.
+    ///
+    /// Header and cookie parameters return `QuerylizerError::UnsupportedLocation`.
+    pub fn parameter<T>(
+        &mut self,
+        spec: &ParameterSpec,
+        value: &T,
+    ) -> Result<&mut Self, QuerylizerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        match spec.location {
+            Location::Path => {
+                let s = crate::serialize(spec, value)?;
+                let mut found = false;
+                for segment in &mut self.segments {
+                    if let Segment::Placeholder(name, filled) = segment {
+                        if *name == spec.name {
+                            *filled = Some(s.clone());
+                            found = true;
+                        }
+                    }
+                }
+                if !found {
+                    return Err(QuerylizerError::UnknownPlaceholder(spec.name.to_owned()));
+                }
+            }
+            Location::Query => {
+                let separator = if self.query.is_empty() { "" } else { "&" };
+                crate::query::append(&mut self.query, separator, |output| {
+                    spec.extend(output, value)
+                })?;
+            }
+            location => return Err(QuerylizerError::UnsupportedLocation(location)),
+        }
+        Ok(self)
+    }
+
+    /// Create the URL.
+    ///
+    /// Returns `QuerylizerError::MissingPlaceholder` if a placeholder has not been filled.
+    pub fn build(&self) -> Result<String, QuerylizerError> {
+        let mut output = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(s) => output.push_str(s),
+                Segment::Placeholder(_, Some(s)) => output.push_str(s),
+                Segment::Placeholder(name, None) => {
+                    return Err(QuerylizerError::MissingPlaceholder((*name).to_owned()));
+                }
+            }
+        }
+        if !self.query.is_empty() {
+            match output.find('?') {
+                None => output.push('?'),
+                Some(i) if i + 1 < output.len() && !output.ends_with('&') => output.push('&'),
+                Some(_) => {}
+            }
+            output.push_str(&self.query);
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Location, ParameterSpec, QuerylizerError, Style};
+
+    use super::UrlBuilder;
+
+    #[test]
+    fn test_path() -> Result<(), QuerylizerError> {
+        let mut url = UrlBuilder::new("/v1/users/{id}/posts/{post}");
+        url.path("id", &12)?.path("post", "a b")?;
+        assert_eq!(url.build()?, "/v1/users/12/posts/a%20b");
+        Ok(())
+    }
+
+    #[test]
+    fn test_path_repeated() -> Result<(), QuerylizerError> {
+        let mut url = UrlBuilder::new("/{id}/{id}");
+        url.path("id", "x")?;
+        assert_eq!(url.build()?, "/x/x");
+        url.path("id", "y")?;
+        assert_eq!(url.build()?, "/y/y");
+        Ok(())
+    }
+
+    #[test]
+    fn test_path_styles() -> Result<(), QuerylizerError> {
+        let mut url = UrlBuilder::new("/colors{label}{matrix}");
+        url.parameter(
+            &ParameterSpec::new("label", Location::Path).style(Style::Label),
+            &["blue", "black"],
+        )?;
+        url.parameter(
+            &ParameterSpec::new("matrix", Location::Path)
+                .style(Style::Matrix)
+                .explode(true),
+            &["blue", "black"],
+        )?;
+        assert_eq!(url.build()?, "/colors.blue,black;matrix=blue;matrix=black");
+        Ok(())
+    }
+
+    #[test]
+    fn test_query() -> Result<(), QuerylizerError> {
+        let mut url = UrlBuilder::new("/search");
+        assert_eq!(url.build()?, "/search");
+        url.query("q", "a&b")?;
+        assert_eq!(url.build()?, "/search?q=a%26b");
+        let mut filter = std::collections::BTreeMap::new();
+        filter.insert("color", "red");
+        url.parameter(
+            &ParameterSpec::new("filter", Location::Query).style(Style::DeepObject),
+            &filter,
+        )?;
+        assert_eq!(url.build()?, "/search?q=a%26b&filter[color]=red");
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_in_template() -> Result<(), QuerylizerError> {
+        let mut url = UrlBuilder::new("/search?v=1");
+        url.query("q", "x")?;
+        assert_eq!(url.build()?, "/search?v=1&q=x");
+        let mut url = UrlBuilder::new("/search?");
+        url.query("q", "x")?;
+        assert_eq!(url.build()?, "/search?q=x");
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_error() -> Result<(), QuerylizerError> {
+        let mut url = UrlBuilder::new("/search");
+        url.query("q", "x")?;
+        assert!(url.query("r", &vec![vec![1]]).is_err());
+        assert_eq!(url.build()?, "/search?q=x");
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_placeholder() {
+        let mut url = UrlBuilder::new("/v1/users/{id}");
+        assert_eq!(
+            url.path("user", &12).err(),
+            Some(QuerylizerError::UnknownPlaceholder("user".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_missing_placeholder() {
+        let url = UrlBuilder::new("/v1/users/{id}");
+        assert_eq!(
+            url.build(),
+            Err(QuerylizerError::MissingPlaceholder("id".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_unsupported_location() {
+        let mut url = UrlBuilder::new("/");
+        assert_eq!(
+            url.parameter(&ParameterSpec::new("x-id", Location::Header), &1)
+                .err(),
+            Some(QuerylizerError::UnsupportedLocation(Location::Header))
+        );
+    }
+
+    #[test]
+    fn test_unbalanced_braces() -> Result<(), QuerylizerError> {
+        let url = UrlBuilder::new("/a}/{b");
+        assert_eq!(url.build()?, "/a}/{b");
+        Ok(())
+    }
+}